@@ -0,0 +1,356 @@
+// audio.rs
+// Background music playback that can follow the show clock around.
+// Without the `audio` feature nothing here makes a sound and the show runs
+// on the wall clock.
+
+#[cfg(feature = "audio")]
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use std::fmt;
+#[cfg(feature = "audio")]
+use std::fs;
+#[cfg(feature = "audio")]
+use std::io::Cursor;
+use std::io::{self, Write};
+use std::path::Path;
+use std::path::PathBuf;
+#[cfg(feature = "audio")]
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "audio")]
+use std::thread;
+use std::time::Duration;
+#[cfg(feature = "audio")]
+use std::time::Instant;
+
+#[cfg(feature = "audio")]
+use crate::clock::Clock;
+use crate::clock::RealClock;
+
+// How often and how far the show clock may drift from the music
+#[cfg(feature = "audio")]
+const SYNC_INTERVAL: Duration = Duration::from_millis(50);
+#[cfg(feature = "audio")]
+const SYNC_TOLERANCE: Duration = Duration::from_millis(20);
+// The reported position lags behind for a moment after starting or seeking
+#[cfg(feature = "audio")]
+const SYNC_SETTLE: Duration = Duration::from_millis(250);
+
+// The show's own track, left out when built without `embed-music`
+#[cfg(feature = "embed-music")]
+const EMBEDDED_MUSIC: &[u8] = include_bytes!("../music/ending.mp3");
+
+// Encoded audio in any format rodio can decode
+pub type MusicData = Arc<[u8]>;
+
+#[derive(Debug)]
+pub enum MusicError {
+    Read(PathBuf, std::io::Error),
+    #[cfg(feature = "audio")]
+    Decode(PathBuf, rodio::decoder::DecoderError),
+    // Built without embedded music and no file given
+    #[cfg(all(feature = "audio", not(feature = "embed-music")))]
+    NoMusic,
+    #[cfg(not(feature = "audio"))]
+    CompiledOut,
+}
+
+impl fmt::Display for MusicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MusicError::Read(path, e) => write!(f, "cannot read {}: {}", path.display(), e),
+            #[cfg(feature = "audio")]
+            MusicError::Decode(path, e) => {
+                write!(f, "cannot play {}: {}", path.display(), e)
+            }
+            #[cfg(all(feature = "audio", not(feature = "embed-music")))]
+            MusicError::NoMusic => write!(
+                f,
+                "this build has no embedded music, pass --music <file> or --mute-music"
+            ),
+            #[cfg(not(feature = "audio"))]
+            MusicError::CompiledOut => write!(f, "sound support was compiled out of this build"),
+        }
+    }
+}
+
+impl std::error::Error for MusicError {}
+
+// The sound device could not play the music
+#[derive(Debug)]
+pub enum AudioError {
+    #[cfg(feature = "audio")]
+    NoDevice(rodio::StreamError),
+    #[cfg(feature = "audio")]
+    Sink(rodio::PlayError),
+    #[cfg(feature = "audio")]
+    Decode(rodio::decoder::DecoderError),
+    #[cfg(not(feature = "audio"))]
+    CompiledOut,
+}
+
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "audio")]
+            AudioError::NoDevice(e) => write!(f, "no audio output device: {}", e),
+            #[cfg(feature = "audio")]
+            AudioError::Sink(e) => write!(f, "cannot play on the audio device: {}", e),
+            #[cfg(feature = "audio")]
+            AudioError::Decode(e) => write!(f, "cannot decode the music: {}", e),
+            #[cfg(not(feature = "audio"))]
+            AudioError::CompiledOut => write!(f, "sound support was compiled out of this build"),
+        }
+    }
+}
+
+impl std::error::Error for AudioError {}
+
+// An open sound device, set up before the show so a missing one can be
+// reported while the terminal is still readable
+#[cfg(feature = "audio")]
+pub struct AudioOutput {
+    handle: OutputStreamHandle,
+}
+
+#[cfg(feature = "audio")]
+pub fn open_output() -> Result<AudioOutput, AudioError> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        // The output stream stops playing when dropped, so it lives here
+        match OutputStream::try_default() {
+            Ok((_stream, handle)) => {
+                let _ = sender.send(Ok(handle));
+                loop {
+                    thread::park();
+                }
+            }
+            Err(e) => {
+                let _ = sender.send(Err(AudioError::NoDevice(e)));
+            }
+        }
+    });
+
+    let handle = receiver.recv().expect("Audio output thread died")?;
+    Ok(AudioOutput { handle })
+}
+
+// Load the music from `path`, or the embedded track when there is none
+#[cfg(feature = "audio")]
+pub fn load_music(path: Option<&Path>) -> Result<MusicData, MusicError> {
+    let Some(path) = path else {
+        #[cfg(feature = "embed-music")]
+        return Ok(Arc::from(EMBEDDED_MUSIC));
+        #[cfg(not(feature = "embed-music"))]
+        return Err(MusicError::NoMusic);
+    };
+
+    let data: MusicData = fs::read(path)
+        .map_err(|e| MusicError::Read(path.to_path_buf(), e))?
+        .into();
+    // Find out now rather than when the music cue comes
+    Decoder::new(Cursor::new(data.clone()))
+        .map_err(|e| MusicError::Decode(path.to_path_buf(), e))?;
+    Ok(data)
+}
+
+#[cfg(not(feature = "audio"))]
+pub fn load_music(_path: Option<&Path>) -> Result<MusicData, MusicError> {
+    Err(MusicError::CompiledOut)
+}
+
+// The music file as it is, for carrying along rather than playing: `path`,
+// or the embedded track when there is none. Nothing to decode, so it works
+// in any build.
+pub fn read_music(path: Option<&Path>) -> Result<Option<MusicData>, MusicError> {
+    match path {
+        Some(path) => match std::fs::read(path) {
+            Ok(data) => Ok(Some(data.into())),
+            Err(e) => Err(MusicError::Read(path.to_path_buf(), e)),
+        },
+        #[cfg(feature = "embed-music")]
+        None => Ok(Some(Arc::from(EMBEDDED_MUSIC))),
+        #[cfg(not(feature = "embed-music"))]
+        None => Ok(None),
+    }
+}
+
+// Write the music as a 16-bit WAV file, `lead` of silence first so it lines
+// up with a show that starts the music that far in
+#[cfg(feature = "audio")]
+pub fn write_wav<W: Write>(data: &MusicData, lead: Duration, mut out: W) -> io::Result<()> {
+    let decoder = Decoder::new(Cursor::new(data.clone()))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, AudioError::Decode(e)))?;
+    let channels = decoder.channels();
+    let rate = decoder.sample_rate();
+    let silence = (lead.as_secs_f64() * rate as f64).round() as usize * channels as usize;
+    let samples: Vec<i16> = std::iter::repeat_n(0, silence).chain(decoder).collect();
+
+    let bytes = samples.len() as u32 * 2;
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + bytes).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    // Plain PCM
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&channels.to_le_bytes())?;
+    out.write_all(&rate.to_le_bytes())?;
+    out.write_all(&(rate * channels as u32 * 2).to_le_bytes())?;
+    out.write_all(&(channels * 2).to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&bytes.to_le_bytes())?;
+    for sample in samples {
+        out.write_all(&sample.to_le_bytes())?;
+    }
+    out.flush()
+}
+
+#[cfg(not(feature = "audio"))]
+pub fn write_wav<W: Write>(_data: &MusicData, _lead: Duration, _out: W) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        AudioError::CompiledOut,
+    ))
+}
+
+#[cfg(feature = "audio")]
+pub struct Music {
+    sink: Sink,
+    data: MusicData,
+    // Show time the track started at
+    start: Duration,
+    // Rewound to before the music cue, waiting for the show to reach it again
+    held: bool,
+    // When the track was last started or seeked, and the clock seek it followed
+    positioned: Instant,
+    seeks: u64,
+}
+
+// Music is started by the lyric loop but paused by the keyboard thread
+pub type SharedMusic = Arc<Mutex<Option<Music>>>;
+
+#[cfg(feature = "audio")]
+impl Music {
+    // Play `data` as the track starting at show time `start`
+    pub fn start(
+        output: &AudioOutput,
+        data: MusicData,
+        start: Duration,
+        seeks: u64,
+    ) -> Result<Self, AudioError> {
+        let sink = Sink::try_new(&output.handle).map_err(AudioError::Sink)?;
+        let source = Decoder::new(Cursor::new(data.clone())).map_err(AudioError::Decode)?;
+        sink.append(source);
+        Ok(Self {
+            sink,
+            data,
+            start,
+            held: false,
+            positioned: Instant::now(),
+            seeks,
+        })
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        if paused || self.held {
+            self.sink.pause();
+        } else {
+            self.sink.play();
+        }
+    }
+
+    // Stop at the start of the track until the next seek
+    pub fn hold(&mut self) {
+        self.held = true;
+        self.sink.pause();
+        let _ = self.sink.try_seek(Duration::ZERO);
+    }
+
+    // Jump to where the track is at show time `now`, reloading it if it
+    // already ended
+    pub fn seek(&mut self, now: Duration, seeks: u64) {
+        self.held = false;
+        if self.sink.empty() {
+            if let Ok(source) = Decoder::new(Cursor::new(self.data.clone())) {
+                self.sink.append(source);
+            }
+        }
+        let _ = self.sink.try_seek(now.saturating_sub(self.start));
+        self.positioned = Instant::now();
+        self.seeks = seeks;
+    }
+
+    // Show time of the samples played so far, while the track is playing
+    fn show_time(&self) -> Option<Duration> {
+        if self.held || self.sink.empty() || self.positioned.elapsed() < SYNC_SETTLE {
+            return None;
+        }
+        Some(self.start + self.sink.get_pos())
+    }
+}
+
+// Keep the show clock on the music's playback position, so device latency
+// and decoder start-up don't make the lyrics drift away from the song.
+// A positive `offset_ms` delays the lyrics, a negative one makes them earlier.
+#[cfg(feature = "audio")]
+pub fn start_sync(clock: Arc<RealClock>, music: SharedMusic, offset_ms: i64) {
+    thread::spawn(move || loop {
+        thread::sleep(SYNC_INTERVAL);
+
+        let music = music.lock().unwrap();
+        let Some(music) = music.as_ref() else {
+            continue;
+        };
+        // Stale until the music caught up with the latest seek
+        if clock.is_paused() || music.seeks != clock.seeks() {
+            continue;
+        }
+        let Some(audio_time) = music.show_time() else {
+            continue;
+        };
+
+        let offset = Duration::from_millis(offset_ms.unsigned_abs());
+        let target = if offset_ms >= 0 {
+            audio_time.saturating_sub(offset)
+        } else {
+            audio_time + offset
+        };
+        let now = clock.now();
+        if now.abs_diff(target) > SYNC_TOLERANCE {
+            clock.correct(target);
+        }
+    });
+}
+
+// Stand-ins while sound support is compiled out, the music never starts
+#[cfg(not(feature = "audio"))]
+pub struct AudioOutput;
+
+#[cfg(not(feature = "audio"))]
+pub fn open_output() -> Result<AudioOutput, AudioError> {
+    Err(AudioError::CompiledOut)
+}
+
+#[cfg(not(feature = "audio"))]
+pub struct Music;
+
+#[cfg(not(feature = "audio"))]
+impl Music {
+    pub fn start(
+        _output: &AudioOutput,
+        _data: MusicData,
+        _start: Duration,
+        _seeks: u64,
+    ) -> Result<Self, AudioError> {
+        Err(AudioError::CompiledOut)
+    }
+
+    pub fn set_paused(&self, _paused: bool) {}
+
+    pub fn hold(&mut self) {}
+
+    pub fn seek(&mut self, _now: Duration, _seeks: u64) {}
+}
+
+#[cfg(not(feature = "audio"))]
+pub fn start_sync(_clock: Arc<RealClock>, _music: SharedMusic, _offset_ms: i64) {}
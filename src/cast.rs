@@ -0,0 +1,148 @@
+// cast.rs
+// asciinema v2 recordings: a JSON header line, then one JSON array per burst
+// of output with the seconds since the start, e.g.
+//
+//     {"version": 2, "width": 80, "height": 24, "env": {"TERM": "xterm"}}
+//     [0.104000, "o", "\u001b[?1049h"]
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+pub struct Cast<W: Write> {
+    out: W,
+    // The start of a character cut off at the end of the last output
+    pending: Vec<u8>,
+}
+
+impl<W: Write> Cast<W> {
+    // Start a recording of a `columns` x `lines` terminal of type `term`,
+    // made at `timestamp` (seconds since the Unix epoch) if it matters
+    pub fn new(
+        mut out: W,
+        columns: u16,
+        lines: u16,
+        term: &str,
+        timestamp: Option<u64>,
+    ) -> io::Result<Self> {
+        let timestamp = match timestamp {
+            Some(timestamp) => format!(", \"timestamp\": {}", timestamp),
+            None => String::new(),
+        };
+        writeln!(
+            out,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}{}, \"env\": {{\"TERM\": {}}}}}",
+            columns,
+            lines,
+            timestamp,
+            json_string(term)
+        )?;
+        Ok(Self {
+            out,
+            pending: Vec::new(),
+        })
+    }
+
+    // Record `data` as sent at `time`
+    pub fn output(&mut self, time: Duration, data: &[u8]) -> io::Result<()> {
+        self.pending.extend_from_slice(data);
+        // JSON strings are text, a character split between two writes waits
+        // for its end
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        if complete == 0 {
+            return Ok(());
+        }
+        let text = String::from_utf8_lossy(&self.pending[..complete]).into_owned();
+        self.pending.drain(..complete);
+        writeln!(
+            self.out,
+            "[{:.6}, \"o\", {}]",
+            time.as_secs_f64(),
+            json_string(&text)
+        )
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+pub fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            ch if (ch as u32) < 0x20 || ch == '\u{7f}' => {
+                json.push_str(&format!("\\u{:04x}", ch as u32))
+            }
+            ch => json.push(ch),
+        }
+    }
+    json.push('"');
+    json
+}
+
+// Passes everything written on to `out` and records it, timed from when
+// recording started
+pub struct Recording<W: Write, C: Write> {
+    out: W,
+    cast: Cast<C>,
+    start: Instant,
+}
+
+impl<W: Write, C: Write> Recording<W, C> {
+    pub fn new(out: W, cast: Cast<C>) -> Self {
+        Self {
+            out,
+            cast,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl<W: Write, C: Write> Write for Recording<W, C> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.out.write(buf)?;
+        self.cast.output(self.start.elapsed(), &buf[..written])?;
+        Ok(written)
+    }
+
+    // The show may be quit at any time, the recording is kept up to date
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()?;
+        self.cast.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_output_as_json_lines() {
+        let mut recorded = Vec::new();
+        let mut cast = Cast::new(&mut recorded, 80, 24, "xterm", None).unwrap();
+        cast.output(Duration::from_millis(250), b"\x1b[2J\"Still\"\r\n")
+            .unwrap();
+        // A block character cut in two comes out whole
+        cast.output(Duration::from_millis(500), &"█".as_bytes()[..1])
+            .unwrap();
+        cast.output(Duration::from_millis(510), &"█".as_bytes()[1..])
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(recorded).unwrap(),
+            "{\"version\": 2, \"width\": 80, \"height\": 24, \"env\": {\"TERM\": \"xterm\"}}\n\
+             [0.250000, \"o\", \"\\u001b[2J\\\"Still\\\"\\r\\n\"]\n\
+             [0.510000, \"o\", \"█\"]\n"
+        );
+    }
+}
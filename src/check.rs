@@ -0,0 +1,292 @@
+// check.rs
+// Lint a show timeline before anyone has to sit through it
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::data::ASCII_ART;
+use crate::layout::TerminalLayout;
+use crate::timeline::{Cue, Lyric};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+pub struct Problem {
+    pub severity: Severity,
+    pub index: usize,
+    pub time: u32,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(
+            f,
+            "{}: cue {} at {}.{:02}s: {}",
+            severity,
+            self.index,
+            self.time / 100,
+            self.time % 100,
+            self.message
+        )
+    }
+}
+
+// Walk the timeline the way the show plays it and report everything that
+// would be drawn wrong or not at all
+pub fn check_timeline(lyrics: &[Lyric], layout: &TerminalLayout) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut report = |severity, index: usize, message: String| {
+        problems.push(Problem {
+            severity,
+            index,
+            time: lyrics[index].time,
+            message,
+        })
+    };
+
+    let mut x = 0usize;
+    let mut y = 0usize;
+    let mut end = None;
+
+    for (index, lyric) in lyrics.iter().enumerate() {
+        if let Some(end_index) = end {
+            report(
+                Severity::Warning,
+                index,
+                format!("never played, the show ends at cue {}", end_index),
+            );
+            continue;
+        }
+
+        if index > 0 && lyric.time < lyrics[index - 1].time {
+            report(
+                Severity::Error,
+                index,
+                format!(
+                    "time goes backwards from {}.{:02}s",
+                    lyrics[index - 1].time / 100,
+                    lyrics[index - 1].time % 100
+                ),
+            );
+        }
+
+        // The show sleeps for the interval, NaN or endless waits can't be
+        if Duration::try_from_secs_f32(lyric.interval.abs()).is_err() {
+            report(
+                Severity::Error,
+                index,
+                format!(
+                    "interval {} is not a valid number of seconds",
+                    lyric.interval
+                ),
+            );
+        }
+
+        match &lyric.cue {
+            Cue::Line { text, newline } => {
+                let width = text.chars().count();
+                if width > 0 && y >= layout.lyrics.height as usize {
+                    report(
+                        Severity::Error,
+                        index,
+                        format!(
+                            "page overflow, line {} does not fit in {} lines",
+                            y + 1,
+                            layout.lyrics.height
+                        ),
+                    );
+                }
+                if x + width > layout.lyrics.width as usize {
+                    report(
+                        Severity::Error,
+                        index,
+                        format!(
+                            "line is {} columns wide, only {} fit",
+                            x + width,
+                            layout.lyrics.width
+                        ),
+                    );
+                }
+                x += width;
+                if *newline {
+                    x = 0;
+                    y += 1;
+                }
+            }
+            Cue::ShowArt(art_index) => {
+                if *art_index >= ASCII_ART.len() {
+                    report(
+                        Severity::Error,
+                        index,
+                        format!(
+                            "art index {} out of range, there are {} pieces of art",
+                            art_index,
+                            ASCII_ART.len()
+                        ),
+                    );
+                }
+            }
+            Cue::ClearLyrics => {
+                x = 0;
+                y = 0;
+            }
+            Cue::StartMusic | Cue::StartCredits => {}
+            Cue::End => end = Some(index),
+        }
+    }
+
+    if end.is_none() && !lyrics.is_empty() {
+        report(
+            Severity::Warning,
+            lyrics.len() - 1,
+            "no end cue, the show stops after the last cue".to_string(),
+        );
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{LayoutKind, LayoutOptions};
+    use crate::script::parse_script;
+
+    // Messages of the problems found in `source`, on an 80x24 screen
+    fn problems(lyrics: &[Lyric]) -> Vec<String> {
+        check_timeline(lyrics, &TerminalLayout::full(80, 24))
+            .iter()
+            .map(|problem| problem.to_string())
+            .collect()
+    }
+
+    fn check(source: &str) -> Vec<String> {
+        problems(&parse_script(source).unwrap())
+    }
+
+    #[test]
+    fn clean_show_has_no_problems() {
+        assert!(check("0 -1 0 \"Hi\"\n100 0 2 \"3\"\n200 0 9\n").is_empty());
+    }
+
+    #[test]
+    fn time_going_backwards() {
+        assert_eq!(
+            check("200 0 0 \"Hi\"\n100 0 0 \"There\"\n300 0 9\n"),
+            ["error: cue 1 at 1.00s: time goes backwards from 2.00s"]
+        );
+    }
+
+    #[test]
+    fn page_overflow() {
+        let mut source = String::new();
+        for _ in 0..23 {
+            source.push_str("0 0 0 \"line\"\n");
+        }
+        source.push_str("0 0 9\n");
+        assert_eq!(
+            check(&source),
+            ["error: cue 22 at 0.00s: page overflow, line 23 does not fit in 22 lines"]
+        );
+    }
+
+    #[test]
+    fn line_too_wide() {
+        let width = TerminalLayout::full(80, 24).lyrics.width as usize;
+        let source = format!(
+            "0 0 1 \"{}\"\n0 0 0 \"more\"\n0 0 9\n",
+            "x".repeat(width - 3)
+        );
+        assert_eq!(
+            check(&source),
+            [format!(
+                "error: cue 1 at 0.00s: line is {} columns wide, only {} fit",
+                width + 1,
+                width
+            )]
+        );
+    }
+
+    #[test]
+    fn checked_against_the_layout_given() {
+        let options = LayoutOptions {
+            kind: Some(LayoutKind::Stacked),
+            ..LayoutOptions::default()
+        };
+        let layout = TerminalLayout::fit(options, 40, 15).unwrap();
+        let lines = layout.lyrics.height as usize + 1;
+        let mut source = "0 0 0 \"line\"\n".repeat(lines);
+        source.push_str("0 0 9\n");
+        let lyrics = parse_script(&source).unwrap();
+        // Fits 80x24, not the shorter page
+        assert!(problems(&lyrics).is_empty());
+        let problems: Vec<String> = check_timeline(&lyrics, &layout)
+            .iter()
+            .map(|problem| problem.to_string())
+            .collect();
+        assert_eq!(
+            problems,
+            [format!(
+                "error: cue {} at 0.00s: page overflow, line {} does not fit in {} lines",
+                lines - 1,
+                lines,
+                lines - 1
+            )]
+        );
+    }
+
+    #[test]
+    fn art_out_of_range() {
+        assert_eq!(
+            check("0 0 2 \"10\"\n100 0 9\n"),
+            ["error: cue 0 at 0.00s: art index 10 out of range, there are 10 pieces of art"]
+        );
+    }
+
+    #[test]
+    fn invalid_interval() {
+        let lyrics = [
+            Lyric::new(
+                Cue::Line {
+                    text: "Hi".to_string(),
+                    newline: true,
+                },
+                0,
+                f32::NAN,
+            ),
+            Lyric::new(Cue::ClearLyrics, 100, f32::INFINITY),
+            Lyric::new(Cue::End, 200, 0.0),
+        ];
+        assert_eq!(
+            problems(&lyrics),
+            [
+                "error: cue 0 at 0.00s: interval NaN is not a valid number of seconds",
+                "error: cue 1 at 1.00s: interval inf is not a valid number of seconds",
+            ]
+        );
+    }
+
+    #[test]
+    fn cues_after_the_end() {
+        assert_eq!(
+            check("0 0 9\n100 0 3\n"),
+            ["warning: cue 1 at 1.00s: never played, the show ends at cue 0"]
+        );
+    }
+
+    #[test]
+    fn missing_end_cue() {
+        assert_eq!(
+            check("0 -1 0 \"Hi\"\n"),
+            ["warning: cue 0 at 0.00s: no end cue, the show stops after the last cue"]
+        );
+    }
+}
@@ -0,0 +1,317 @@
+// cli.rs
+// Command line options
+
+use std::path::PathBuf;
+
+use crate::layout::{
+    LayoutKind, LayoutOptions, TerminalLayout, MAX_ART_SCALE, MIN_COLUMNS, MIN_LINES,
+};
+use crate::raster::ImageFormat;
+use crate::render::MAX_FPS;
+use crate::serial::BAUD_RATES;
+
+// What `render` makes of the show
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderFormat {
+    // asciinema v2 recording
+    Cast,
+    // Animated SVG
+    Svg,
+    // A picture of every frame
+    Frames(ImageFormat),
+}
+
+pub struct Options {
+    // `render` subcommand: write a recording of the show without playing it
+    pub render: bool,
+    // `export --html` subcommand: write the show as a web page
+    pub export: bool,
+    pub html: bool,
+    // Where `render` and `export` write to, stdout when not given
+    pub output: Option<PathBuf>,
+    pub format: RenderFormat,
+    // Where `render` saves the pictures, and how many a second of the show
+    pub frames: Option<PathBuf>,
+    pub fps: u32,
+    // Record the live show as an asciinema cast
+    pub record: Option<PathBuf>,
+    pub show_version: bool,
+    pub no_music: bool,
+    pub script: Option<PathBuf>,
+    pub music: Option<PathBuf>,
+    pub check: bool,
+    // Play without music instead of asking when the sound device fails
+    pub continue_muted: bool,
+    // Terminal type to draw for instead of the one in $TERM
+    pub term_profile: Option<String>,
+    // Draw on this device (e.g. a serial port) instead of stdout
+    pub serial: Option<PathBuf>,
+    pub baud: u32,
+    // Terminal size to draw for instead of asking the terminal
+    pub size: Option<(u16, u16)>,
+    // Layout to use, stage size and art scale
+    pub layout: LayoutOptions,
+    // Color theme, and a theme file to use instead of the one in the config
+    // directory
+    pub theme: Option<String>,
+    pub theme_file: Option<PathBuf>,
+    // CRT phosphor effects
+    pub crt: bool,
+    // Print the screen at this show time (centiseconds) and exit
+    pub dump_frame: Option<u32>,
+    // Milliseconds to delay the lyrics against the music
+    pub audio_offset: i64,
+}
+
+impl Options {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Options {
+            render: false,
+            export: false,
+            html: false,
+            output: None,
+            format: RenderFormat::Cast,
+            frames: None,
+            fps: 30,
+            record: None,
+            show_version: false,
+            no_music: false,
+            script: None,
+            music: None,
+            check: false,
+            continue_muted: false,
+            term_profile: None,
+            serial: None,
+            baud: 19200,
+            size: None,
+            layout: LayoutOptions::default(),
+            theme: None,
+            theme_file: None,
+            crt: false,
+            dump_frame: None,
+            audio_offset: 0,
+        };
+
+        let mut args = args.iter().skip(1).peekable();
+        match args.peek().map(|arg| arg.as_str()) {
+            Some("render") => options.render = true,
+            Some("export") => options.export = true,
+            _ => {}
+        }
+        if options.render || options.export {
+            args.next();
+        }
+        let mut format_given = false;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-V" | "--version" => options.show_version = true,
+                "-M" | "--mute-music" => options.no_music = true,
+                "-s" | "--script" => {
+                    let path = args.next().ok_or("--script requires a file path")?;
+                    options.script = Some(PathBuf::from(path));
+                }
+                "-m" | "--music" => {
+                    let path = args.next().ok_or("--music requires a file path")?;
+                    options.music = Some(PathBuf::from(path));
+                }
+                "-c" | "--check" => options.check = true,
+                "--continue-muted" => options.continue_muted = true,
+                "--term-profile" => {
+                    let name = args
+                        .next()
+                        .ok_or("--term-profile requires a terminal type")?;
+                    options.term_profile = Some(name.clone());
+                }
+                "--audio-offset" => {
+                    let value = args.next().ok_or("--audio-offset requires milliseconds")?;
+                    options.audio_offset = value
+                        .parse()
+                        .map_err(|_| format!("Invalid --audio-offset: {}", value))?;
+                }
+                "--serial" => {
+                    let path = args.next().ok_or("--serial requires a device path")?;
+                    options.serial = Some(PathBuf::from(path));
+                }
+                "--baud" => {
+                    let value = args.next().ok_or("--baud requires a line rate")?;
+                    options.baud = value
+                        .parse()
+                        .ok()
+                        .filter(|baud| BAUD_RATES.contains(baud))
+                        .ok_or_else(|| format!("Invalid --baud: {}", value))?;
+                }
+                "--size" => {
+                    let value = args.next().ok_or("--size requires COLUMNSxLINES")?;
+                    options.size = Some(parse_size(value)?);
+                }
+                "--layout" => {
+                    let value = args.next().ok_or("--layout requires a layout name")?;
+                    let layout = LayoutKind::from_name(value).ok_or_else(|| {
+                        format!(
+                            "Unknown --layout: {} (use full, stacked or lyrics-only)",
+                            value
+                        )
+                    })?;
+                    options.layout.kind = Some(layout);
+                }
+                "--stage" => {
+                    // The size is optional, the classic 80x24 by default
+                    let size = match args.peek() {
+                        Some(value) if value.starts_with(|c: char| c.is_ascii_digit()) => {
+                            parse_size(args.next().unwrap())?
+                        }
+                        _ => (MIN_COLUMNS, MIN_LINES),
+                    };
+                    options.layout.stage = Some(size);
+                }
+                "--art-scale" => {
+                    let value = args.next().ok_or("--art-scale requires a factor")?;
+                    options.layout.art_scale = value
+                        .parse()
+                        .ok()
+                        .filter(|scale| (1..=MAX_ART_SCALE).contains(scale))
+                        .ok_or_else(|| {
+                            format!(
+                                "Invalid --art-scale: {} (use 1 to {})",
+                                value, MAX_ART_SCALE
+                            )
+                        })?;
+                }
+                "--theme" => {
+                    let name = args.next().ok_or("--theme requires a theme name")?;
+                    options.theme = Some(name.clone());
+                }
+                "--theme-file" => {
+                    let path = args.next().ok_or("--theme-file requires a file path")?;
+                    options.theme_file = Some(PathBuf::from(path));
+                }
+                "--crt" => options.crt = true,
+                "--record" => {
+                    let path = args.next().ok_or("--record requires a file path")?;
+                    options.record = Some(PathBuf::from(path));
+                }
+                "--format" if options.render => {
+                    let value = args.next().ok_or("--format requires a format name")?;
+                    options.format = match value.as_str() {
+                        "cast" => RenderFormat::Cast,
+                        "svg" => RenderFormat::Svg,
+                        "png" => RenderFormat::Frames(ImageFormat::Png),
+                        "ppm" => RenderFormat::Frames(ImageFormat::Ppm),
+                        _ => {
+                            return Err(format!(
+                                "Unknown --format: {} (use cast, svg, png or ppm)",
+                                value
+                            ))
+                        }
+                    };
+                    format_given = true;
+                }
+                "--frames" if options.render => {
+                    let path = args.next().ok_or("--frames requires a directory")?;
+                    options.frames = Some(PathBuf::from(path));
+                }
+                "--fps" if options.render => {
+                    let value = args.next().ok_or("--fps requires a frame rate")?;
+                    options.fps = value
+                        .parse()
+                        .ok()
+                        .filter(|fps| (1..=MAX_FPS).contains(fps))
+                        .ok_or_else(|| {
+                            format!("Invalid --fps: {} (use 1 to {})", value, MAX_FPS)
+                        })?;
+                }
+                "--html" if options.export => options.html = true,
+                "-o" | "--output" if options.render || options.export => {
+                    let path = args.next().ok_or("--output requires a file path")?;
+                    options.output = Some(PathBuf::from(path));
+                }
+                "--dump-frame" => {
+                    let value = args.next().ok_or("--dump-frame requires a show time")?;
+                    let time = parse_show_time(value)
+                        .ok_or_else(|| format!("Invalid --dump-frame: {}", value))?;
+                    options.dump_frame = Some(time);
+                }
+                other => return Err(format!("Unknown argument: {}", other)),
+            }
+        }
+
+        if options.export && !options.html {
+            return Err("export needs a format: --html".to_string());
+        }
+        // Pictures go in the --frames directory, PNGs unless told otherwise
+        match (options.format, &options.frames) {
+            (RenderFormat::Frames(_), Some(_)) => {}
+            (_, Some(_)) if !format_given => {
+                options.format = RenderFormat::Frames(ImageFormat::Png)
+            }
+            (_, Some(_)) => return Err("--frames needs --format png or ppm".to_string()),
+            (RenderFormat::Frames(_), None) => {
+                return Err("--format png and ppm need --frames <dir>".to_string())
+            }
+            _ => {}
+        }
+        Ok(options)
+    }
+}
+
+// `80x24` style terminal size, no smaller than the show needs
+fn parse_size(value: &str) -> Result<(u16, u16), String> {
+    let size = value
+        .split_once('x')
+        .and_then(|(columns, lines)| Some((columns.parse().ok()?, lines.parse().ok()?)));
+    let (min_columns, min_lines) = TerminalLayout::min_size(LayoutOptions::default());
+    match size {
+        Some((columns, lines)) if columns >= min_columns && lines >= min_lines => {
+            Ok((columns, lines))
+        }
+        Some(_) => Err(format!(
+            "--size must be at least {}x{}: {}",
+            min_columns, min_lines, value
+        )),
+        None => Err(format!("Invalid --size: {}", value)),
+    }
+}
+
+// Show time in seconds (`83.5`) or minutes and seconds (`1:23.5`), returned
+// in centiseconds
+fn parse_show_time(value: &str) -> Option<u32> {
+    let (minutes, seconds) = match value.split_once(':') {
+        Some((minutes, seconds)) => (minutes.parse::<u32>().ok()?, seconds),
+        None => (0, value),
+    };
+    let seconds = seconds
+        .parse::<f64>()
+        .ok()
+        .filter(|s| s.is_finite() && *s >= 0.0)?;
+    let centiseconds = (seconds * 100.0).round();
+    if centiseconds > u32::MAX as f64 {
+        return None;
+    }
+    minutes.checked_mul(6000)?.checked_add(centiseconds as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_show_times() {
+        assert_eq!(parse_show_time("83.5"), Some(8350));
+        assert_eq!(parse_show_time("1:23.50"), Some(8350));
+        assert_eq!(parse_show_time("0"), Some(0));
+        assert_eq!(parse_show_time("0.004"), Some(0));
+        assert_eq!(parse_show_time("2:05"), Some(12500));
+
+        for bad in [
+            "", "soon", "-1", "1:-5", "x:10", "inf", "NaN", "1:inf", "1e300",
+        ] {
+            assert_eq!(parse_show_time(bad), None, "{}", bad);
+        }
+        // Too far to count in centiseconds
+        assert_eq!(parse_show_time("999999:0"), None);
+        assert_eq!(parse_show_time("715828:0"), None);
+        assert_eq!(parse_show_time("715827:52.96"), None);
+        assert_eq!(parse_show_time("715827:52.95"), Some(u32::MAX));
+        assert_eq!(parse_show_time("42949672.96"), None);
+    }
+}
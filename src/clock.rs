@@ -0,0 +1,298 @@
+// clock.rs
+// Show clock shared by the lyric loop, art drawing and the credits thread:
+// the wall clock for the live show, a stepped one for rendering it
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+pub trait Clock: Send + Sync {
+    // Show time elapsed since the clock started
+    fn now(&self) -> Duration;
+
+    // Block until the show time reaches `deadline`, returns false when a seek
+    // interrupted the wait
+    fn sleep_until(&self, deadline: Duration) -> bool;
+
+    fn sleep(&self, duration: Duration) -> bool {
+        self.sleep_until(self.now() + duration)
+    }
+
+    // Jump to another show time, waking everyone asleep on the clock
+    fn seek(&self, time: Duration);
+
+    // Number of seeks so far, so drawing code can notice it has to redraw
+    fn seeks(&self) -> u64;
+
+    fn set_paused(&self, paused: bool);
+
+    fn is_paused(&self) -> bool;
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+struct RealState {
+    // Show time at `base`
+    offset: Duration,
+    base: Instant,
+    paused: bool,
+    seeks: u64,
+}
+
+impl RealState {
+    fn now(&self) -> Duration {
+        if self.paused {
+            self.offset
+        } else {
+            self.offset + self.base.elapsed()
+        }
+    }
+}
+
+// Wall clock time, starting when created
+pub struct RealClock {
+    state: Mutex<RealState>,
+    changed: Condvar,
+}
+
+impl RealClock {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(RealState {
+                offset: Duration::ZERO,
+                base: Instant::now(),
+                paused: false,
+                seeks: 0,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+impl RealClock {
+    // Nudge the clock to `time` without it counting as a seek, sleepers just
+    // wake up a little earlier or later
+    pub fn correct(&self, time: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.offset = time;
+        state.base = Instant::now();
+        self.changed.notify_all();
+    }
+}
+
+impl Clock for RealClock {
+    fn now(&self) -> Duration {
+        self.state.lock().unwrap().now()
+    }
+
+    fn sleep_until(&self, deadline: Duration) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let seeks = state.seeks;
+        loop {
+            if state.seeks != seeks {
+                return false;
+            }
+            let now = state.now();
+            if now >= deadline {
+                return true;
+            }
+            state = if state.paused {
+                self.changed.wait(state).unwrap()
+            } else {
+                self.changed.wait_timeout(state, deadline - now).unwrap().0
+            };
+        }
+    }
+
+    fn seek(&self, time: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.offset = time;
+        state.base = Instant::now();
+        state.seeks += 1;
+        self.changed.notify_all();
+    }
+
+    fn seeks(&self) -> u64 {
+        self.state.lock().unwrap().seeks
+    }
+
+    fn set_paused(&self, paused: bool) {
+        let mut state = self.state.lock().unwrap();
+        if state.paused == paused {
+            return;
+        }
+        state.offset = state.now();
+        state.base = Instant::now();
+        state.paused = paused;
+        self.changed.notify_all();
+    }
+
+    fn is_paused(&self) -> bool {
+        self.state.lock().unwrap().paused
+    }
+}
+
+// Clock that only moves when told to, sleepers wake once it is stepped past
+// their deadline
+pub struct ManualClock {
+    state: Mutex<ManualState>,
+    changed: Condvar,
+}
+
+struct ManualState {
+    now: Duration,
+    seeks: u64,
+    // Deadline of everyone asleep on the clock, and the seeks when they went
+    // to sleep
+    sleeping: Vec<(Duration, u64)>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(ManualState {
+                now: Duration::ZERO,
+                seeks: 0,
+                sleeping: Vec::new(),
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    pub fn set(&self, time: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.now = time;
+        self.changed.notify_all();
+    }
+
+    // Wait until everyone else holding the clock is asleep on it with nothing
+    // due yet. With every thread of a show holding a clone, that is once the
+    // show did everything it had to up to now.
+    pub fn wait_idle(self: &Arc<Self>) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let others = Arc::strong_count(self) - 1;
+            let idle = state
+                .sleeping
+                .iter()
+                .filter(|&&(deadline, seeks)| deadline > state.now && seeks == state.seeks)
+                .count();
+            if idle >= others {
+                return;
+            }
+            // Threads ending let go of the clock without a word
+            state = self
+                .changed
+                .wait_timeout(state, Duration::from_millis(1))
+                .unwrap()
+                .0;
+        }
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.state.lock().unwrap().now
+    }
+
+    fn sleep_until(&self, deadline: Duration) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let seeks = state.seeks;
+        if state.now >= deadline {
+            return true;
+        }
+        state.sleeping.push((deadline, seeks));
+        self.changed.notify_all();
+        while state.now < deadline && state.seeks == seeks {
+            state = self.changed.wait(state).unwrap();
+        }
+        if let Some(i) = state.sleeping.iter().position(|&s| s == (deadline, seeks)) {
+            state.sleeping.swap_remove(i);
+        }
+        state.seeks == seeks
+    }
+
+    fn seek(&self, time: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.now = time;
+        state.seeks += 1;
+        self.changed.notify_all();
+    }
+
+    fn seeks(&self) -> u64 {
+        self.state.lock().unwrap().seeks
+    }
+
+    // A manual clock never moves on its own, so there is nothing to pause
+    fn set_paused(&self, _paused: bool) {}
+
+    fn is_paused(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn manual_clock_wakes_sleepers_when_stepped() {
+        let clock = Arc::new(ManualClock::new());
+        let sleeper = {
+            let clock = clock.clone();
+            thread::spawn(move || {
+                clock.sleep_until(Duration::from_secs(5));
+                clock.now()
+            })
+        };
+
+        clock.set(Duration::from_secs(2));
+        assert!(!sleeper.is_finished());
+        clock.set(Duration::from_secs(7));
+        assert_eq!(sleeper.join().unwrap(), Duration::from_secs(7));
+    }
+
+    #[test]
+    fn manual_clock_does_not_block_for_past_deadlines() {
+        let clock = ManualClock::new();
+        clock.set(Duration::from_secs(3));
+        assert!(clock.sleep_until(Duration::from_secs(1)));
+        assert_eq!(clock.now(), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn manual_clock_knows_when_everyone_is_asleep() {
+        let clock = Arc::new(ManualClock::new());
+        let sleeper = {
+            let clock: SharedClock = clock.clone();
+            thread::spawn(move || {
+                clock.sleep_until(Duration::from_secs(1));
+                clock.sleep_until(Duration::from_secs(3))
+            })
+        };
+
+        // Asleep until 1s, then again until 3s once stepped past it
+        clock.wait_idle();
+        clock.set(Duration::from_secs(2));
+        clock.wait_idle();
+        assert!(!sleeper.is_finished());
+        clock.set(Duration::from_secs(3));
+        assert!(sleeper.join().unwrap());
+        // Nobody else is left to wait for
+        clock.wait_idle();
+    }
+
+    #[test]
+    fn paused_real_clock_stands_still() {
+        let clock = RealClock::new();
+        clock.set_paused(true);
+        let paused_at = clock.now();
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(clock.now(), paused_at);
+
+        clock.seek(Duration::from_secs(60));
+        assert_eq!(clock.now(), Duration::from_secs(60));
+        assert_eq!(clock.seeks(), 1);
+    }
+}
@@ -0,0 +1,323 @@
+// compositor.rs
+// The show is drawn into a back buffer, split into regions (frame, lyrics,
+// credits, art). Presenting it sends only the cells that differ from what
+// the terminal already shows, so slow links carry a fraction of the bytes
+// and nothing is blanked just to be drawn again. Every cell remembers what
+// it belongs to and how bright it is, so it is sent in the right colors.
+
+use std::io;
+
+use crate::renderer::Renderer;
+use crate::theme::{Element, Palette};
+
+// Unchanged cells between two changes that are cheaper to print again than
+// to move the cursor over (a move is about as long as this)
+const MAX_GAP: usize = 6;
+
+// A rectangle of cells, 1-based like the rest of the drawing code
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Region {
+    pub fn new(x: u16, y: u16, width: u16, height: u16) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+// What a character is printed as: its element and shade
+type Ink = (Element, i8);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Cell {
+    ch: char,
+    element: Element,
+    // Steps brighter (or darker when negative) than the element's color
+    shade: i8,
+}
+
+impl Cell {
+    // A blank looks the same whatever it belongs to, the background is
+    // shared
+    fn looks_like(self, other: Cell) -> bool {
+        self.ch == other.ch && self.ink() == other.ink()
+    }
+
+    fn ink(self) -> Option<Ink> {
+        (self.ch != ' ').then_some((self.element, self.shade))
+    }
+}
+
+const BLANK: Cell = Cell {
+    ch: ' ',
+    element: Element::Frame,
+    shade: 0,
+};
+
+pub struct Compositor {
+    // What the screen should look like
+    back: Vec<Vec<Cell>>,
+    // What the terminal shows, `None` where that isn't known
+    front: Vec<Vec<Option<Cell>>>,
+    // Element and shade whose colors the terminal is set to, if known
+    pen: Option<Ink>,
+    // Shade added to everything, e.g. to dim the whole picture
+    overall_shade: i8,
+    // Something was drawn since the terminal was last brought up to date
+    stale: bool,
+}
+
+impl Compositor {
+    pub fn new(columns: u16, lines: u16) -> Self {
+        Self {
+            back: vec![vec![BLANK; columns as usize]; lines as usize],
+            front: vec![vec![None; columns as usize]; lines as usize],
+            pen: None,
+            overall_shade: 0,
+            stale: true,
+        }
+    }
+
+    // Columns and lines
+    pub fn size(&self) -> (u16, u16) {
+        (self.back[0].len() as u16, self.back.len() as u16)
+    }
+
+    // The terminal was cleared, both buffers are blank
+    pub fn cleared(&mut self) {
+        for row in self.back.iter_mut() {
+            row.fill(BLANK);
+        }
+        for row in self.front.iter_mut() {
+            row.fill(Some(BLANK));
+        }
+    }
+
+    // Put `text` at (`x`, `y`) as part of `element`, cutting off whatever
+    // falls outside `region` or the screen
+    pub fn print(&mut self, region: Region, x: u16, y: u16, text: &str, element: Element) {
+        let right = region.x as usize + region.width as usize;
+        let bottom = region.y as usize + region.height as usize;
+        let (x, y) = (x as usize, y as usize);
+        if y < region.y as usize || y >= bottom || y == 0 || y > self.back.len() {
+            return;
+        }
+        self.stale = true;
+        let row = &mut self.back[y - 1];
+        for (i, ch) in text.chars().enumerate() {
+            let column = x + i;
+            if column >= right || column > row.len() {
+                break;
+            }
+            if column >= region.x as usize && column > 0 && !ch.is_control() {
+                row[column - 1] = Cell {
+                    ch,
+                    element,
+                    shade: 0,
+                };
+            }
+        }
+    }
+
+    // Blank the whole of `region`
+    pub fn clear(&mut self, region: Region) {
+        let blank = " ".repeat(region.width as usize);
+        for y in region.y..region.y + region.height {
+            self.print(region, region.x, y, &blank, BLANK.element);
+        }
+    }
+
+    // Make the cell at (`x`, `y`) `shade` steps brighter than its element
+    pub fn shade(&mut self, x: u16, y: u16, shade: i8) {
+        if x == 0 || y == 0 {
+            return;
+        }
+        let cell = self
+            .back
+            .get_mut(y as usize - 1)
+            .and_then(|row| row.get_mut(x as usize - 1));
+        if let Some(cell) = cell {
+            cell.shade = shade;
+            self.stale = true;
+        }
+    }
+
+    pub fn set_overall_shade(&mut self, shade: i8) {
+        self.stale |= self.overall_shade != shade;
+        self.overall_shade = shade;
+    }
+
+    // Bring the terminal up to date with the back buffer in `palette`'s
+    // colors, returns whether anything was drawn (and the cursor moved)
+    pub fn present(&mut self, renderer: &mut dyn Renderer, palette: &Palette) -> io::Result<bool> {
+        if !self.stale {
+            return Ok(false);
+        }
+        let mut drawn = false;
+        for (y, (back, front)) in self.back.iter().zip(self.front.iter_mut()).enumerate() {
+            let back: Vec<Cell> = back
+                .iter()
+                .map(|cell| Cell {
+                    shade: cell.shade + self.overall_shade,
+                    ..*cell
+                })
+                .collect();
+            for (start, end, ink) in changed_runs(&back, front) {
+                // Blanks can go out in whatever colors are set
+                if let Some(ink) = ink.filter(|ink| self.pen != Some(*ink)) {
+                    if let Some((foreground, background)) = palette.colors(ink.0, ink.1) {
+                        renderer.set_colors(foreground, background)?;
+                    }
+                    self.pen = Some(ink);
+                }
+                let run: String = back[start..end].iter().map(|cell| cell.ch).collect();
+                renderer.move_to(start as u16 + 1, y as u16 + 1)?;
+                renderer.print(&run)?;
+                for column in start..end {
+                    front[column] = Some(back[column]);
+                }
+                drawn = true;
+            }
+        }
+        self.stale = false;
+        Ok(drawn)
+    }
+}
+
+// Spans of a row that need printing and the element and shade they are
+// printed in (`None` for blanks), running on through short stretches of
+// unchanged cells printed the same
+fn changed_runs(back: &[Cell], front: &[Option<Cell>]) -> Vec<(usize, usize, Option<Ink>)> {
+    let changed = |x: usize| !front[x].is_some_and(|shown| shown.looks_like(back[x]));
+    let mut runs: Vec<(usize, usize, Option<Ink>)> = Vec::new();
+    for x in (0..back.len()).filter(|&x| changed(x)) {
+        if let Some((_, end, run_ink)) = runs.last_mut() {
+            if x - *end <= MAX_GAP {
+                let mut joined = *run_ink;
+                let same_colors = back[*end..=x]
+                    .iter()
+                    .all(|cell| match (cell.ink(), joined) {
+                        (Some(ink), Some(joined)) => ink == joined,
+                        (ink, _) => {
+                            joined = joined.or(ink);
+                            true
+                        }
+                    });
+                if same_colors {
+                    *end = x + 1;
+                    *run_ink = joined;
+                    continue;
+                }
+            }
+        }
+        runs.push((x, x + 1, back[x].ink()));
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::{built_in_themes, ColorDepth};
+    use crossterm::style::Color;
+
+    // Keeps what would be sent to the terminal
+    #[derive(Default)]
+    struct Recorder {
+        sent: Vec<String>,
+    }
+
+    impl Renderer for Recorder {
+        fn move_to(&mut self, x: u16, y: u16) -> io::Result<()> {
+            self.sent.push(format!("@{},{}", x, y));
+            Ok(())
+        }
+
+        fn print(&mut self, text: &str) -> io::Result<()> {
+            self.sent.push(text.to_string());
+            Ok(())
+        }
+
+        fn clear_all(&mut self) -> io::Result<()> {
+            self.sent.push("clear".to_string());
+            Ok(())
+        }
+
+        fn set_colors(&mut self, foreground: Color, _background: Color) -> io::Result<()> {
+            self.sent.push(format!("{:?}", foreground));
+            Ok(())
+        }
+
+        fn reset_colors(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn presents_only_changed_cells() {
+        let screen = Region::new(1, 1, 20, 3);
+        let pane = Region::new(3, 2, 5, 2);
+        let mut compositor = Compositor::new(20, 3);
+        let mut recorder = Recorder::default();
+        let palette = Palette::monochrome();
+        compositor.cleared();
+
+        // Clipped to the pane
+        compositor.print(pane, 3, 2, "Hello, world", Element::Lyrics);
+        assert!(compositor.present(&mut recorder, &palette).unwrap());
+        assert_eq!(recorder.sent, ["@3,2", "Hello"]);
+
+        // Nothing changed, nothing sent
+        recorder.sent.clear();
+        compositor.print(pane, 3, 2, "Hello", Element::Lyrics);
+        assert!(!compositor.present(&mut recorder, &palette).unwrap());
+        assert!(recorder.sent.is_empty());
+
+        // Close changes go out as one run, far apart ones as two
+        compositor.print(screen, 1, 1, "a b", Element::Lyrics);
+        compositor.print(screen, 20, 1, "z", Element::Lyrics);
+        compositor.clear(pane);
+        compositor.present(&mut recorder, &palette).unwrap();
+        assert_eq!(
+            recorder.sent,
+            ["@1,1", "a b", "@20,1", "z", "@3,2", "     "]
+        );
+    }
+
+    #[test]
+    fn switches_colors_between_elements() {
+        let screen = Region::new(1, 1, 20, 1);
+        let mut compositor = Compositor::new(20, 1);
+        let mut recorder = Recorder::default();
+        let palette = Palette::new(&built_in_themes()[2], ColorDepth::Ansi16);
+        compositor.cleared();
+
+        compositor.print(screen, 1, 1, "|ab", Element::Frame);
+        compositor.print(screen, 2, 1, "ab", Element::Lyrics);
+        compositor.print(screen, 6, 1, "|", Element::Frame);
+        compositor.present(&mut recorder, &palette).unwrap();
+        assert_eq!(
+            recorder.sent,
+            ["Grey", "@1,1", "|", "White", "@2,1", "ab", "Grey", "@6,1", "|"]
+        );
+
+        // Blanks keep whatever colors are set
+        recorder.sent.clear();
+        compositor.print(screen, 2, 1, "  ", Element::Art);
+        compositor.present(&mut recorder, &palette).unwrap();
+        assert_eq!(recorder.sent, ["@2,1", "  "]);
+    }
+}
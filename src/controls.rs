@@ -0,0 +1,115 @@
+// controls.rs
+// Keyboard controls while the show plays
+//
+// space       pause / resume
+// left/right  previous / next line
+// up/down     previous / next page (also page up / page down)
+// q, esc      quit
+//
+// Resizing the window lays the show out again, making it smaller than the
+// show fits in pauses it until it's big enough again.
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use std::thread;
+use std::time::Duration;
+
+use crate::audio::SharedMusic;
+use crate::clock::SharedClock;
+use crate::timeline::{seek_points, Lyric};
+use crate::tui_draw::{end_draw, ScreenHandle};
+
+// Seeking back within this long of a point goes to the one before it, so
+// pressing left twice keeps moving back (centiseconds)
+const SEEK_BACK_GRACE: u32 = 100;
+
+// Listen for keys on a background thread, returns false when the terminal
+// can't be put into raw mode (e.g. stdin is not a terminal). The show is
+// resized along with the window when `follow_resize` is set.
+pub fn start_controls(
+    screen: ScreenHandle,
+    clock: SharedClock,
+    music: SharedMusic,
+    lyrics: &[Lyric],
+    follow_resize: bool,
+) -> bool {
+    if terminal::enable_raw_mode().is_err() {
+        return false;
+    }
+
+    let lines = seek_points(lyrics, false);
+    let pages = seek_points(lyrics, true);
+
+    // Paused because the window got too small, rather than by the user
+    let mut held = false;
+
+    thread::spawn(move || loop {
+        let key = match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => key,
+            Ok(Event::Resize(columns, lines)) if follow_resize => {
+                let fits = !matches!(
+                    screen.draw_wait(move |screen| screen.resize(columns, lines)),
+                    Some(Ok(false))
+                );
+                if !fits && !clock.is_paused() {
+                    set_paused(&clock, &music, true);
+                    held = true;
+                } else if fits && held {
+                    set_paused(&clock, &music, false);
+                    held = false;
+                }
+                continue;
+            }
+            Ok(_) => continue,
+            Err(_) => break,
+        };
+
+        match key.code {
+            KeyCode::Char(' ') => {
+                set_paused(&clock, &music, !clock.is_paused());
+                held = false;
+            }
+            KeyCode::Left => seek(&clock, &lines, false),
+            KeyCode::Right => seek(&clock, &lines, true),
+            KeyCode::Up | KeyCode::PageUp => seek(&clock, &pages, false),
+            KeyCode::Down | KeyCode::PageDown => seek(&clock, &pages, true),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => quit(&screen),
+            KeyCode::Char('q') | KeyCode::Esc => quit(&screen),
+            _ => {}
+        }
+    });
+
+    true
+}
+
+fn set_paused(clock: &SharedClock, music: &SharedMusic, paused: bool) {
+    clock.set_paused(paused);
+    if let Some(music) = music.lock().unwrap().as_mut() {
+        music.set_paused(paused);
+    }
+}
+
+fn seek(clock: &SharedClock, points: &[u32], forward: bool) {
+    let now = clock.now().as_millis() as u32 / 10;
+    let target = if forward {
+        points.iter().find(|&&p| p > now).copied()
+    } else {
+        Some(
+            points
+                .iter()
+                .rev()
+                .find(|&&p| p + SEEK_BACK_GRACE < now)
+                .copied()
+                .unwrap_or(0),
+        )
+    };
+
+    if let Some(target) = target {
+        clock.seek(Duration::from_millis(target as u64 * 10));
+    }
+}
+
+fn quit(screen: &ScreenHandle) {
+    let _ = end_draw(screen);
+    std::process::exit(0);
+}
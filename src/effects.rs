@@ -0,0 +1,115 @@
+// effects.rs
+// CRT phosphor effects, all following the show clock so they freeze with a
+// pause and replay the same after a seek: freshly typed characters glow and
+// fade back to normal, the picture flickers now and then, and a block cursor
+// blinks where the lyrics are typed.
+
+use std::time::Duration;
+
+// How often the effects are brought up to date
+pub const FRAME: Duration = Duration::from_millis(40);
+
+// Shade of a character just typed, it loses one level every `FADE_STEP`
+pub const GLOW: i8 = 3;
+const FADE_STEP: Duration = Duration::from_millis(80);
+
+// Shade of the whole picture while it flickers
+pub const FLICKER: i8 = -1;
+// Frames in a hundred that flicker
+const FLICKER_CHANCE: u64 = 2;
+
+const BLINK: Duration = Duration::from_millis(530);
+pub const CURSOR: char = '█';
+
+pub struct Effects {
+    now: Duration,
+    // Lyric page cells typed lately (column, line) and when
+    fresh: Vec<(u16, u16, Duration)>,
+}
+
+impl Effects {
+    pub fn new() -> Self {
+        Self {
+            now: Duration::ZERO,
+            fresh: Vec::new(),
+        }
+    }
+
+    // The show clock reads `now`, forget characters that faded
+    pub fn tick(&mut self, now: Duration) {
+        self.now = now;
+        self.fresh.retain(|&(_, _, at)| glow_at(at, now) > 0);
+    }
+
+    pub fn typed(&mut self, x: u16, y: u16) {
+        self.fresh.push((x, y, self.now));
+    }
+
+    // The page was cleared or redrawn, nothing on it is fresh
+    pub fn clear(&mut self) {
+        self.fresh.clear();
+    }
+
+    // Cells of the lyric page still glowing, with their shade
+    pub fn glowing(&self) -> impl Iterator<Item = (u16, u16, i8)> + '_ {
+        self.fresh
+            .iter()
+            .map(|&(x, y, at)| (x, y, glow_at(at, self.now)))
+            .filter(|&(_, _, glow)| glow > 0)
+    }
+
+    pub fn flickers(&self) -> bool {
+        let frame = (self.now.as_millis() / FRAME.as_millis()) as u64;
+        scramble(frame) % 100 < FLICKER_CHANCE
+    }
+
+    pub fn cursor_on(&self) -> bool {
+        (self.now.as_millis() / BLINK.as_millis()).is_multiple_of(2)
+    }
+}
+
+// Shade of a character typed at `at`, seen at `now`
+fn glow_at(at: Duration, now: Duration) -> i8 {
+    let steps = now.saturating_sub(at).as_millis() / FADE_STEP.as_millis();
+    GLOW.saturating_sub(steps.min(GLOW as u128) as i8)
+}
+
+// Spreads frame numbers out so flickers look random but are the same every
+// time the show is played
+fn scramble(frame: u64) -> u64 {
+    let mut x = frame.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    x ^= x >> 31;
+    x.wrapping_mul(0xbf58_476d_1ce4_e5b9) >> 33
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_characters_fade_with_the_show_clock() {
+        let mut effects = Effects::new();
+        effects.tick(Duration::from_millis(1000));
+        effects.typed(4, 1);
+        assert_eq!(effects.glowing().collect::<Vec<_>>(), [(4, 1, GLOW)]);
+
+        effects.tick(Duration::from_millis(1100));
+        assert_eq!(effects.glowing().collect::<Vec<_>>(), [(4, 1, GLOW - 1)]);
+
+        effects.tick(Duration::from_millis(1300));
+        assert_eq!(effects.glowing().count(), 0);
+
+        // A few frames in a hundred flicker, always the same ones
+        let flickers = |effects: &mut Effects| {
+            (0..1000)
+                .filter(|&frame| {
+                    effects.tick(FRAME * frame);
+                    effects.flickers()
+                })
+                .collect::<Vec<_>>()
+        };
+        let first = flickers(&mut effects);
+        assert!((5..50).contains(&first.len()));
+        assert_eq!(first, flickers(&mut effects));
+    }
+}
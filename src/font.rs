@@ -0,0 +1,132 @@
+// font.rs
+// A small bitmap font for printable ASCII, drawn by hand. Every glyph is
+// nine rows of five pixels: capitals and digits take the top seven, the last
+// two are for descenders.
+
+pub const WIDTH: usize = 5;
+pub const HEIGHT: usize = 9;
+
+// Glyphs from ' ' to '~', rows top to bottom, `#` for a lit pixel
+#[rustfmt::skip]
+const GLYPHS: [(char, &str); 95] = [
+    (' ', "..... ..... ..... ..... ..... ..... ..... ..... ....."),
+    ('!', "..#.. ..#.. ..#.. ..#.. ..#.. ..... ..#.. ..... ....."),
+    ('"', ".#.#. .#.#. .#.#. ..... ..... ..... ..... ..... ....."),
+    ('#', ".#.#. .#.#. ##### .#.#. ##### .#.#. .#.#. ..... ....."),
+    ('$', "..#.. .#### #.#.. .###. ..#.# ####. ..#.. ..... ....."),
+    ('%', "##... ##..# ...#. ..#.. .#... #..## ...## ..... ....."),
+    ('&', ".##.. #..#. #.#.. .#... #.#.# #..#. .##.# ..... ....."),
+    ('\'', "..#.. ..#.. .#... ..... ..... ..... ..... ..... ....."),
+    ('(', "...#. ..#.. .#... .#... .#... ..#.. ...#. ..... ....."),
+    (')', ".#... ..#.. ...#. ...#. ...#. ..#.. .#... ..... ....."),
+    ('*', "..... ..#.. #.#.# .###. #.#.# ..#.. ..... ..... ....."),
+    ('+', "..... ..#.. ..#.. ##### ..#.. ..#.. ..... ..... ....."),
+    (',', "..... ..... ..... ..... ..... .##.. ..#.. .#... ....."),
+    ('-', "..... ..... ..... ##### ..... ..... ..... ..... ....."),
+    ('.', "..... ..... ..... ..... ..... .##.. .##.. ..... ....."),
+    ('/', "..... ....# ...#. ..#.. .#... #.... ..... ..... ....."),
+    ('0', ".###. #...# #..## #.#.# ##..# #...# .###. ..... ....."),
+    ('1', "..#.. .##.. ..#.. ..#.. ..#.. ..#.. .###. ..... ....."),
+    ('2', ".###. #...# ....# ...#. ..#.. .#... ##### ..... ....."),
+    ('3', "##### ...#. ..#.. ...#. ....# #...# .###. ..... ....."),
+    ('4', "...#. ..##. .#.#. #..#. ##### ...#. ...#. ..... ....."),
+    ('5', "##### #.... ####. ....# ....# #...# .###. ..... ....."),
+    ('6', "..##. .#... #.... ####. #...# #...# .###. ..... ....."),
+    ('7', "##### ....# ...#. ..#.. .#... .#... .#... ..... ....."),
+    ('8', ".###. #...# #...# .###. #...# #...# .###. ..... ....."),
+    ('9', ".###. #...# #...# .#### ....# ...#. .##.. ..... ....."),
+    (':', "..... .##.. .##.. ..... .##.. .##.. ..... ..... ....."),
+    (';', "..... .##.. .##.. ..... .##.. ..#.. .#... ..... ....."),
+    ('<', "...#. ..#.. .#... #.... .#... ..#.. ...#. ..... ....."),
+    ('=', "..... ..... ##### ..... ##### ..... ..... ..... ....."),
+    ('>', ".#... ..#.. ...#. ....# ...#. ..#.. .#... ..... ....."),
+    ('?', ".###. #...# ....# ...#. ..#.. ..... ..#.. ..... ....."),
+    ('@', ".###. #...# ....# .##.# #.#.# #.#.# .###. ..... ....."),
+    ('A', ".###. #...# #...# ##### #...# #...# #...# ..... ....."),
+    ('B', "####. #...# #...# ####. #...# #...# ####. ..... ....."),
+    ('C', ".###. #...# #.... #.... #.... #...# .###. ..... ....."),
+    ('D', "###.. #..#. #...# #...# #...# #..#. ###.. ..... ....."),
+    ('E', "##### #.... #.... ####. #.... #.... ##### ..... ....."),
+    ('F', "##### #.... #.... ####. #.... #.... #.... ..... ....."),
+    ('G', ".###. #...# #.... #.### #...# #...# .#### ..... ....."),
+    ('H', "#...# #...# #...# ##### #...# #...# #...# ..... ....."),
+    ('I', ".###. ..#.. ..#.. ..#.. ..#.. ..#.. .###. ..... ....."),
+    ('J', "..### ...#. ...#. ...#. ...#. #..#. .##.. ..... ....."),
+    ('K', "#...# #..#. #.#.. ##... #.#.. #..#. #...# ..... ....."),
+    ('L', "#.... #.... #.... #.... #.... #.... ##### ..... ....."),
+    ('M', "#...# ##.## #.#.# #.#.# #...# #...# #...# ..... ....."),
+    ('N', "#...# #...# ##..# #.#.# #..## #...# #...# ..... ....."),
+    ('O', ".###. #...# #...# #...# #...# #...# .###. ..... ....."),
+    ('P', "####. #...# #...# ####. #.... #.... #.... ..... ....."),
+    ('Q', ".###. #...# #...# #...# #.#.# #..#. .##.# ..... ....."),
+    ('R', "####. #...# #...# ####. #.#.. #..#. #...# ..... ....."),
+    ('S', ".#### #.... #.... .###. ....# ....# ####. ..... ....."),
+    ('T', "##### ..#.. ..#.. ..#.. ..#.. ..#.. ..#.. ..... ....."),
+    ('U', "#...# #...# #...# #...# #...# #...# .###. ..... ....."),
+    ('V', "#...# #...# #...# #...# #...# .#.#. ..#.. ..... ....."),
+    ('W', "#...# #...# #...# #.#.# #.#.# #.#.# .#.#. ..... ....."),
+    ('X', "#...# #...# .#.#. ..#.. .#.#. #...# #...# ..... ....."),
+    ('Y', "#...# #...# .#.#. ..#.. ..#.. ..#.. ..#.. ..... ....."),
+    ('Z', "##### ....# ...#. ..#.. .#... #.... ##### ..... ....."),
+    ('[', ".###. .#... .#... .#... .#... .#... .###. ..... ....."),
+    ('\\', "..... #.... .#... ..#.. ...#. ....# ..... ..... ....."),
+    (']', ".###. ...#. ...#. ...#. ...#. ...#. .###. ..... ....."),
+    ('^', "..#.. .#.#. #...# ..... ..... ..... ..... ..... ....."),
+    ('_', "..... ..... ..... ..... ..... ..... ..... ##### ....."),
+    ('`', ".#... ..#.. ...#. ..... ..... ..... ..... ..... ....."),
+    ('a', "..... ..... .###. ....# .#### #...# .#### ..... ....."),
+    ('b', "#.... #.... #.##. ##..# #...# #...# ####. ..... ....."),
+    ('c', "..... ..... .###. #.... #.... #...# .###. ..... ....."),
+    ('d', "....# ....# .##.# #..## #...# #...# .#### ..... ....."),
+    ('e', "..... ..... .###. #...# ##### #.... .###. ..... ....."),
+    ('f', "..##. .#..# .#... ###.. .#... .#... .#... ..... ....."),
+    ('g', "..... ..... .#### #...# #...# #...# .#### ....# .###."),
+    ('h', "#.... #.... #.##. ##..# #...# #...# #...# ..... ....."),
+    ('i', "..#.. ..... .##.. ..#.. ..#.. ..#.. .###. ..... ....."),
+    ('j', "...#. ..... ..##. ...#. ...#. ...#. ...#. #..#. .##.."),
+    ('k', "#.... #.... #..#. #.#.. ##... #.#.. #..#. ..... ....."),
+    ('l', ".##.. ..#.. ..#.. ..#.. ..#.. ..#.. .###. ..... ....."),
+    ('m', "..... ..... ##.#. #.#.# #.#.# #.#.# #.#.# ..... ....."),
+    ('n', "..... ..... #.##. ##..# #...# #...# #...# ..... ....."),
+    ('o', "..... ..... .###. #...# #...# #...# .###. ..... ....."),
+    ('p', "..... ..... ####. #...# #...# #...# ####. #.... #...."),
+    ('q', "..... ..... .#### #...# #...# #...# .#### ....# ....#"),
+    ('r', "..... ..... #.##. ##..# #.... #.... #.... ..... ....."),
+    ('s', "..... ..... .#### #.... .###. ....# ####. ..... ....."),
+    ('t', ".#... .#... ###.. .#... .#... .#..# ..##. ..... ....."),
+    ('u', "..... ..... #...# #...# #...# #..## .##.# ..... ....."),
+    ('v', "..... ..... #...# #...# #...# .#.#. ..#.. ..... ....."),
+    ('w', "..... ..... #...# #...# #.#.# #.#.# .#.#. ..... ....."),
+    ('x', "..... ..... #...# .#.#. ..#.. .#.#. #...# ..... ....."),
+    ('y', "..... ..... #...# #...# #...# #...# .#### ....# .###."),
+    ('z', "..... ..... ##### ...#. ..#.. .#... ##### ..... ....."),
+    ('{', "...## ..#.. ..#.. .#... ..#.. ..#.. ...## ..... ....."),
+    ('|', "..#.. ..#.. ..#.. ..#.. ..#.. ..#.. ..#.. ..#.. ..#.."),
+    ('}', "##... ..#.. ..#.. ...#. ..#.. ..#.. ##... ..... ....."),
+    ('~', "..... ..... .#... #.#.# ...#. ..... ..... ..... ....."),
+];
+
+// Whether pixel (`x`, `y`) of `ch` is lit, None for characters the font
+// doesn't have
+pub fn lit(ch: char, x: usize, y: usize) -> Option<bool> {
+    let (_, rows) = GLYPHS.get((ch as usize).checked_sub(' ' as usize)?)?;
+    Some(rows.as_bytes()[y * (WIDTH + 1) + x] == b'#')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glyphs_are_in_order_and_complete() {
+        for (i, (ch, rows)) in GLYPHS.iter().enumerate() {
+            assert_eq!(*ch as usize, ' ' as usize + i);
+            let rows: Vec<&str> = rows.split(' ').collect();
+            assert_eq!(rows.len(), HEIGHT, "{:?}", ch);
+            assert!(rows.iter().all(|row| row.len() == WIDTH), "{:?}", ch);
+        }
+        assert_eq!(lit('|', 2, 8), Some(true));
+        assert_eq!(lit('A', 0, 0), Some(false));
+        assert_eq!(lit('é', 0, 0), None);
+    }
+}
@@ -0,0 +1,448 @@
+// html.rs
+// The show as one standalone web page: the timeline, art and credits go in
+// as data, a small script types them out like the terminal does, following
+// the music when the page carries it.
+
+use std::io::{self, Write};
+
+use crate::cast::json_string;
+use crate::compositor::Region;
+use crate::data::{ASCII_ART, CREDITS};
+use crate::layout::TerminalLayout;
+use crate::render::show_end;
+use crate::theme::{Rgb, Theme};
+use crate::timeline::{char_interval, Cue, Lyric};
+use crate::tui_draw::CREDITS_DURATION;
+
+// Colors of a theme without any
+const MONOCHROME: [Rgb; 5] = [
+    (0, 0, 0),
+    (192, 192, 192),
+    (192, 192, 192),
+    (192, 192, 192),
+    (192, 192, 192),
+];
+
+// Write the page, with `music` (an encoded audio file) to play along
+pub fn export_html<W: Write>(
+    mut out: W,
+    lyrics: &[Lyric],
+    layout: &TerminalLayout,
+    theme: &Theme,
+    music: Option<&[u8]>,
+) -> io::Result<()> {
+    let [background, frame, lyric, credit, art] = match theme.colors {
+        Some(colors) => [
+            colors.background,
+            colors.frame,
+            colors.lyrics,
+            colors.credits,
+            colors.art,
+        ],
+        None => MONOCHROME,
+    };
+    let css = |rgb: Rgb| format!("#{:02x}{:02x}{:02x}", rgb.0, rgb.1, rgb.2);
+
+    write!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Still Alive</title>\n<style>\n\
+         body {{ background: #111; color: #aaa; font-family: sans-serif; margin: 2em; }}\n\
+         #screen {{ display: inline-block; margin: 0; padding: 0.5em; line-height: 1.2; \
+         font-family: 'DejaVu Sans Mono', Menlo, Consolas, monospace; font-size: 16px; \
+         background: {}; }}\n\
+         .f {{ color: {} }} .l {{ color: {} }} .c {{ color: {} }} .a {{ color: {} }}\n\
+         .cursor {{ animation: blink 1.06s step-end infinite; }}\n\
+         @keyframes blink {{ 50% {{ visibility: hidden }} }}\n\
+         #controls {{ margin-top: 1em; }}\n\
+         #seek {{ width: 40em; vertical-align: middle; }}\n\
+         </style>\n</head>\n<body>\n<pre id=\"screen\"></pre>\n\
+         <div id=\"controls\"><button id=\"play\">Play</button> \
+         <input id=\"seek\" type=\"range\" min=\"0\" step=\"0.01\" value=\"0\"></div>\n",
+        css(background),
+        css(frame),
+        css(lyric),
+        css(credit),
+        css(art)
+    )?;
+    if let Some(music) = music {
+        writeln!(
+            out,
+            "<audio id=\"music\" preload=\"auto\" src=\"data:{};base64,{}\"></audio>",
+            mime_type(music),
+            base64(music)
+        )?;
+    }
+    writeln!(
+        out,
+        "<script>\nconst SHOW = {};\n{}</script>\n</body>\n</html>",
+        show_json(lyrics, layout),
+        PLAYER
+    )?;
+    out.flush()
+}
+
+// Everything the player needs to know about the show, as JSON
+fn show_json(lyrics: &[Lyric], layout: &TerminalLayout) -> String {
+    let region = |region: Option<Region>| match region {
+        Some(r) => format!(
+            "{{\"x\": {}, \"y\": {}, \"width\": {}, \"height\": {}}}",
+            r.x, r.y, r.width, r.height
+        ),
+        None => "null".to_string(),
+    };
+    let cues: Vec<String> = lyrics
+        .iter()
+        .enumerate()
+        .map(|(index, lyric)| match &lyric.cue {
+            Cue::Line { text, newline } => format!(
+                "[{}, \"line\", {}, {}, {}]",
+                lyric.time,
+                script_string(text),
+                newline,
+                char_interval(lyrics, index)
+            ),
+            Cue::ShowArt(art) => format!("[{}, \"art\", {}]", lyric.time, art),
+            Cue::ClearLyrics => format!("[{}, \"clear\"]", lyric.time),
+            Cue::StartMusic => format!("[{}, \"music\"]", lyric.time),
+            Cue::StartCredits => format!("[{}, \"credits\"]", lyric.time),
+            Cue::End => format!("[{}, \"end\"]", lyric.time),
+        })
+        .collect();
+    let art: Vec<String> = ASCII_ART
+        .iter()
+        .map(|art| {
+            let lines: Vec<String> = art.iter().map(|line| script_string(line)).collect();
+            format!("[{}]", lines.join(", "))
+        })
+        .collect();
+    let framed: Vec<String> = layout
+        .framed()
+        .into_iter()
+        .map(|pane| region(Some(pane)))
+        .collect();
+
+    format!(
+        "{{\n\"columns\": {}, \"lines\": {}, \"artScale\": {},\n\
+         \"lyrics\": {}, \"credits\": {}, \"art\": {},\n\"framed\": [{}],\n\
+         \"end\": {}, \"creditsDuration\": {},\n\"cues\": [\n{}\n],\n\
+         \"artwork\": [\n{}\n],\n\"creditsText\": {}\n}}",
+        layout.columns,
+        layout.lines,
+        layout.options.art_scale,
+        region(Some(layout.lyrics)),
+        region(layout.credits),
+        region(layout.art),
+        framed.join(", "),
+        show_end(lyrics),
+        CREDITS_DURATION.as_secs_f64(),
+        cues.join(",\n"),
+        art.join(",\n"),
+        script_string(CREDITS)
+    )
+}
+
+// A JSON string that can't end the <script> block it sits in or start any
+// markup, whatever the lyrics say
+fn script_string(text: &str) -> String {
+    json_string(text)
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026")
+}
+
+// What kind of audio file `music` is, going by its first bytes
+fn mime_type(music: &[u8]) -> &'static str {
+    match music {
+        [b'I', b'D', b'3', ..] | [0xff, 0xe0..=0xff, ..] => "audio/mpeg",
+        [b'O', b'g', b'g', b'S', ..] => "audio/ogg",
+        [b'f', b'L', b'a', b'C', ..] => "audio/flac",
+        [b'R', b'I', b'F', b'F', ..] => "audio/wav",
+        _ => "application/octet-stream",
+    }
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = ((bytes[0] as u32) << 16) | ((bytes[1] as u32) << 8) | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[((bits >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+// Replays the timeline like `state_at` and draws it like `Screen`, following
+// the <audio> element's position once the music started
+const PLAYER: &str = r#"
+const screen = document.getElementById("screen");
+const playButton = document.getElementById("play");
+const seek = document.getElementById("seek");
+const music = document.getElementById("music");
+const credits = Array.from(SHOW.creditsText);
+const musicCue = SHOW.cues.find(cue => cue[1] === "music");
+const musicStart = musicCue ? musicCue[0] / 100 : null;
+const end = SHOW.end / 100;
+
+// Everything on screen at show time `time` (centiseconds)
+function stateAt(time) {
+  const state = { lines: [""], x: 0, y: 0, art: null, credits: null };
+  for (const [cueTime, kind, ...args] of SHOW.cues) {
+    if (time <= cueTime || kind === "end") break;
+    if (kind === "line") {
+      const [text, newline, interval] = args;
+      const chars = Array.from(text);
+      const elapsed = (time - cueTime) / 100;
+      if (interval > 0 && elapsed < interval * chars.length) {
+        const typed = Math.min(chars.length, Math.floor(elapsed / interval) + 1);
+        state.lines[state.lines.length - 1] += chars.slice(0, typed).join("");
+        state.x += typed;
+        break;
+      }
+      state.lines[state.lines.length - 1] += text;
+      state.x += chars.length;
+      if (newline) {
+        state.lines.push("");
+        state.x = 0;
+        state.y += 1;
+      }
+    } else if (kind === "art") {
+      state.art = args[0];
+    } else if (kind === "clear") {
+      state.lines = [""];
+      state.x = 0;
+      state.y = 0;
+    } else if (kind === "credits") {
+      state.credits = cueTime;
+    }
+  }
+  return state;
+}
+
+// Last `height` lines of the credits once `shown` characters are typed
+function creditsTail(height, shown) {
+  const lines = [""];
+  for (const ch of credits.slice(0, shown)) {
+    if (ch === "\n") {
+      lines.push("");
+      if (lines.length > height) lines.shift();
+    } else {
+      lines[lines.length - 1] += ch;
+    }
+  }
+  return lines;
+}
+
+// The screen at show time `time` (centiseconds), as rows of [character, class]
+function screenAt(time) {
+  const cells = Array.from({ length: SHOW.lines }, () =>
+    Array.from({ length: SHOW.columns }, () => [" ", ""]));
+  const put = (region, x, y, text, kind) => {
+    if (y < region.y || y >= region.y + region.height || y < 1 || y > SHOW.lines) return;
+    Array.from(text).forEach((ch, i) => {
+      const column = x + i;
+      // Control characters (the script's \0 pauses) take a column but
+      // leave it as it was
+      if (column >= region.x && column < region.x + region.width && column >= 1 &&
+          column <= SHOW.columns && ch >= " " && ch !== "\x7f") {
+        cells[y - 1][column - 1] = [ch, kind];
+      }
+    });
+  };
+  const whole = { x: 1, y: 1, width: SHOW.columns, height: SHOW.lines };
+
+  for (const pane of SHOW.framed) {
+    const edge = " " + "-".repeat(pane.width) + " ";
+    put(whole, pane.x - 1, pane.y - 1, edge, "f");
+    for (let y = pane.y; y < pane.y + pane.height; y++) {
+      put(whole, pane.x - 1, y, "|", "f");
+      put(whole, pane.x + pane.width, y, "|", "f");
+    }
+    put(whole, pane.x - 1, pane.y + pane.height, edge, "f");
+  }
+
+  const state = stateAt(time);
+  const lyrics = SHOW.lyrics;
+  const top = Math.max(0, state.y + 1 - lyrics.height);
+  state.lines.slice(top, top + lyrics.height).forEach((line, dy) => {
+    put(lyrics, lyrics.x, lyrics.y + dy, line, "l");
+  });
+  const cursorX = lyrics.x + Math.min(state.x, lyrics.width - 1);
+  const cursorY = lyrics.y + state.y - top;
+  if (cells[cursorY - 1] && cells[cursorY - 1][cursorX - 1]) {
+    cells[cursorY - 1][cursorX - 1][1] += " cursor";
+  }
+
+  if (SHOW.art && state.art !== null && SHOW.artwork[state.art]) {
+    const scale = SHOW.artScale;
+    SHOW.artwork[state.art].forEach((line, dy) => {
+      const wide = Array.from(line).map(ch => ch.repeat(scale)).join("");
+      for (let k = 0; k < scale; k++) {
+        put(SHOW.art, SHOW.art.x, SHOW.art.y + dy * scale + k, wide, "a");
+      }
+    });
+  }
+
+  if (SHOW.credits && state.credits !== null) {
+    const elapsed = time / 100 - state.credits / 100;
+    const shown = Math.min(credits.length,
+      Math.floor(elapsed / SHOW.creditsDuration * credits.length) + 1);
+    const region = SHOW.credits;
+    const tail = creditsTail(region.height, shown);
+    tail.forEach((line, k) => {
+      put(region, region.x, region.y + region.height - tail.length + k, line, "c");
+    });
+  }
+  return cells;
+}
+
+function toHtml(cells) {
+  const escape = text => text.replace(/&/g, "&amp;").replace(/</g, "&lt;");
+  return cells.map(row => {
+    let html = "";
+    let run = "";
+    let kind = null;
+    for (const [ch, cellKind] of row) {
+      if (cellKind !== kind) {
+        if (run) html += kind ? `<span class="${kind}">${escape(run)}</span>` : escape(run);
+        run = "";
+        kind = cellKind;
+      }
+      run += ch;
+    }
+    if (run) html += kind ? `<span class="${kind}">${escape(run)}</span>` : escape(run);
+    return html;
+  }).join("\n");
+}
+
+// The show clock, in seconds: the music's position while it plays, the
+// page's own clock otherwise
+let playing = false;
+let showTime = 0;
+let clockAt = 0;
+let musicFailed = false;
+let drawn = null;
+
+function play() {
+  if (showTime >= end) jump(0);
+  playing = true;
+  clockAt = performance.now();
+  playButton.textContent = "Pause";
+}
+
+function pause() {
+  playing = false;
+  if (music) music.pause();
+  playButton.textContent = "Play";
+}
+
+function jump(time) {
+  showTime = time;
+  clockAt = performance.now();
+  if (music && musicStart !== null) {
+    music.pause();
+    music.currentTime = Math.max(0, time - musicStart);
+  }
+}
+
+function tick() {
+  const now = performance.now();
+  if (music && !music.paused) {
+    showTime = musicStart + music.currentTime;
+  } else if (playing) {
+    showTime += (now - clockAt) / 1000;
+  }
+  clockAt = now;
+
+  if (playing && music && music.paused && !music.ended && !musicFailed &&
+      musicStart !== null && showTime >= musicStart) {
+    music.currentTime = showTime - musicStart;
+    music.play().catch(() => { musicFailed = true; });
+  }
+  if (showTime >= end) {
+    showTime = end;
+    pause();
+  }
+
+  seek.value = showTime;
+  const html = toHtml(screenAt(showTime * 100));
+  if (html !== drawn) {
+    screen.innerHTML = html;
+    drawn = html;
+  }
+  requestAnimationFrame(tick);
+}
+
+seek.max = end;
+seek.addEventListener("input", () => jump(parseFloat(seek.value)));
+playButton.addEventListener("click", () => playing ? pause() : play());
+document.addEventListener("keydown", event => {
+  if (event.key === " ") {
+    event.preventDefault();
+    playing ? pause() : play();
+  }
+});
+requestAnimationFrame(tick);
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script::parse_script;
+    use crate::theme::built_in_themes;
+
+    #[test]
+    fn page_carries_the_show_and_the_music() {
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+
+        let lyrics = parse_script("100 -1 0 \"Hi\"\n300 0 9\n").unwrap();
+        let layout = TerminalLayout::full(80, 24);
+        let theme = &built_in_themes()[0];
+        let page = |music| {
+            let mut page = Vec::new();
+            export_html(&mut page, &lyrics, &layout, theme, music).unwrap();
+            String::from_utf8(page).unwrap()
+        };
+
+        let silent = page(None);
+        // Typing spread until the next cue, one second a character
+        assert!(silent.contains("[100, \"line\", \"Hi\", true, 1]"));
+        assert!(silent.contains(".l { color: #ffb000 }"));
+        assert!(!silent.contains("<audio"));
+
+        let with_music = page(Some(b"ID3\x04"));
+        assert!(with_music.contains("src=\"data:audio/mpeg;base64,SUQzBA==\""));
+    }
+
+    #[test]
+    fn lyrics_cannot_break_out_of_the_script() {
+        let lyrics = parse_script("100 -1 0 \"</script><b>Hi & bye\"\n300 0 9\n").unwrap();
+        let mut page = Vec::new();
+        let theme = &built_in_themes()[0];
+        export_html(
+            &mut page,
+            &lyrics,
+            &TerminalLayout::full(80, 24),
+            theme,
+            None,
+        )
+        .unwrap();
+        let page = String::from_utf8(page).unwrap();
+
+        assert_eq!(page.matches("</script>").count(), 1);
+        assert!(page.contains("\"\\u003c/script\\u003e\\u003cb\\u003eHi \\u0026 bye\""));
+    }
+}
@@ -0,0 +1,354 @@
+// layout.rs
+// Where the lyrics, credits and art go on a terminal of a given size. The
+// classic layout needs 80x24, smaller terminals (phones over SSH, tmux
+// splits) get the art and credits under the lyrics, or the lyrics alone.
+// On big terminals the show can keep to a fixed-size stage in the middle.
+
+use crossterm::terminal;
+use std::process::exit;
+
+use crate::compositor::Region;
+use crate::data::ASCII_ART;
+
+// Smallest terminal the classic layout fits in
+pub const MIN_COLUMNS: u16 = 80;
+pub const MIN_LINES: u16 = 24;
+
+// Largest --art-scale, beyond that the art outgrows any real terminal
+pub const MAX_ART_SCALE: u16 = 4;
+
+// Lines of credits in the stacked layout
+const STACKED_CREDITS_LINES: u16 = 4;
+
+// Fewest lyric lines worth stacking the art under, fewer and the art is left
+// out
+const STACKED_MIN_LYRIC_LINES: u16 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LayoutKind {
+    // Lyrics on the left, credits and art on the right
+    Full,
+    // Lyrics on top, credits and (room permitting) art below
+    Stacked,
+    LyricsOnly,
+}
+
+impl LayoutKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "full" => Some(Self::Full),
+            "stacked" => Some(Self::Stacked),
+            "lyrics-only" => Some(Self::LyricsOnly),
+            _ => None,
+        }
+    }
+
+    // Smallest terminal the layout fits in with the art `art_scale` times
+    // its size, the stacked one is as wide as the longest lyric line
+    pub fn min_size(self, art_scale: u16) -> (u16, u16) {
+        let (art_width, art_height) = art_size(art_scale);
+        match self {
+            Self::Full => (std::cmp::max(MIN_COLUMNS, art_width + 40), art_height + 4),
+            Self::Stacked => (36, STACKED_MIN_LYRIC_LINES + STACKED_CREDITS_LINES + 3),
+            Self::LyricsOnly => (20, 5),
+        }
+    }
+
+    fn fits(self, columns: u16, lines: u16, art_scale: u16) -> bool {
+        let (min_columns, min_lines) = self.min_size(art_scale);
+        columns >= min_columns && lines >= min_lines
+    }
+}
+
+// How the show should be laid out, whatever size the terminal turns out to
+// be
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayoutOptions {
+    // Layout to use, `None` picks the roomiest one that fits
+    pub kind: Option<LayoutKind>,
+    // Keep to this many columns and lines in the middle of a bigger terminal
+    pub stage: Option<(u16, u16)>,
+    // Draw every character of the art as a block this many cells across
+    // and down
+    pub art_scale: u16,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            kind: None,
+            stage: None,
+            art_scale: 1,
+        }
+    }
+}
+
+// Terminal dimensions and where everything goes on it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerminalLayout {
+    pub kind: LayoutKind,
+    pub options: LayoutOptions,
+    pub columns: u16,
+    pub lines: u16,
+    // The part of the terminal the show is on
+    pub stage: Region,
+    pub lyrics: Region,
+    // Not every layout has room for these
+    pub credits: Option<Region>,
+    pub art: Option<Region>,
+}
+
+impl TerminalLayout {
+    // Layout for a terminal of `size`, or our own terminal's size, exits
+    // when it is too small for the layout asked for (or any)
+    pub fn new(size: Option<(u16, u16)>, options: LayoutOptions) -> Self {
+        let (columns, lines) =
+            size.unwrap_or_else(|| terminal::size().unwrap_or((MIN_COLUMNS, MIN_LINES)));
+        match Self::fit(options, columns, lines) {
+            Some(layout) => layout,
+            None => {
+                let (min_columns, min_lines) = Self::min_size(options);
+                eprintln!(
+                    "The terminal is {}x{}, the show needs at least {}x{}",
+                    columns, lines, min_columns, min_lines
+                );
+                exit(1);
+            }
+        }
+    }
+
+    // The layout asked for, or the roomiest one, for a `columns` x `lines`
+    // terminal. `None` when it doesn't fit.
+    pub fn fit(options: LayoutOptions, columns: u16, lines: u16) -> Option<Self> {
+        // A stage bigger than the terminal shrinks to it
+        let (width, height) = match options.stage {
+            Some((width, height)) => (columns.min(width), lines.min(height)),
+            None => (columns, lines),
+        };
+        let scale = options.art_scale;
+        let kind = match options.kind {
+            Some(kind) => Some(kind).filter(|kind| kind.fits(width, height, scale)),
+            None => [
+                LayoutKind::Full,
+                LayoutKind::Stacked,
+                LayoutKind::LyricsOnly,
+            ]
+            .into_iter()
+            .find(|kind| kind.fits(width, height, scale)),
+        }?;
+        let mut layout = match kind {
+            LayoutKind::Full => Self::full_scaled(width, height, scale),
+            LayoutKind::Stacked => Self::stacked(width, height, scale),
+            LayoutKind::LyricsOnly => Self::lyrics_only(width, height),
+        };
+        layout.options = options;
+        layout.center(columns, lines);
+        Some(layout)
+    }
+
+    // Smallest terminal the layout asked for (or any) fits in
+    pub fn min_size(options: LayoutOptions) -> (u16, u16) {
+        options
+            .kind
+            .unwrap_or(LayoutKind::LyricsOnly)
+            .min_size(options.art_scale)
+    }
+
+    // The classic layout for a terminal of at least 80 * 24
+    #[cfg(test)]
+    pub fn full(columns: u16, lines: u16) -> Self {
+        Self::full_scaled(columns, lines, 1)
+    }
+
+    fn full_scaled(columns: u16, lines: u16, art_scale: u16) -> Self {
+        let (art_width, art_height) = art_size(art_scale);
+        // Wide enough for the art to fit under the credits
+        let credits_width = std::cmp::min(columns - 43, std::cmp::max(56, art_width - 3));
+        let credits_height = lines - art_height - 2;
+        let lyric_width = columns - 8 - credits_width;
+        let lyric_height = lines - 2;
+
+        Self {
+            kind: LayoutKind::Full,
+            options: LayoutOptions::default(),
+            columns,
+            lines,
+            stage: Region::new(1, 1, columns, lines),
+            lyrics: Region::new(2, 2, lyric_width, lyric_height),
+            credits: Some(Region::new(
+                lyric_width + 4,
+                2,
+                credits_width,
+                credits_height,
+            )),
+            art: Some(Region::new(
+                lyric_width + 3,
+                credits_height + 3,
+                art_width,
+                art_height,
+            )),
+        }
+    }
+
+    // Lyrics over the credits, with the art centered underneath when there
+    // is room for it and enough lyrics
+    fn stacked(columns: u16, lines: u16, art_scale: u16) -> Self {
+        let (art_width, art_height) = art_size(art_scale);
+        let frame_lines = STACKED_CREDITS_LINES + 3;
+        let art_fits =
+            columns >= art_width && lines >= frame_lines + STACKED_MIN_LYRIC_LINES + art_height;
+        let art_height = if art_fits { art_height } else { 0 };
+        let lyric_height = lines - frame_lines - art_height;
+        let credits_y = lyric_height + 3;
+
+        Self {
+            kind: LayoutKind::Stacked,
+            options: LayoutOptions::default(),
+            columns,
+            lines,
+            stage: Region::new(1, 1, columns, lines),
+            lyrics: Region::new(2, 2, columns - 2, lyric_height),
+            credits: Some(Region::new(
+                2,
+                credits_y,
+                columns - 2,
+                STACKED_CREDITS_LINES,
+            )),
+            art: art_fits.then(|| {
+                Region::new(
+                    (columns - art_width) / 2 + 1,
+                    credits_y + STACKED_CREDITS_LINES + 1,
+                    art_width,
+                    art_height,
+                )
+            }),
+        }
+    }
+
+    fn lyrics_only(columns: u16, lines: u16) -> Self {
+        Self {
+            kind: LayoutKind::LyricsOnly,
+            options: LayoutOptions::default(),
+            columns,
+            lines,
+            stage: Region::new(1, 1, columns, lines),
+            lyrics: Region::new(2, 2, columns - 2, lines - 2),
+            credits: None,
+            art: None,
+        }
+    }
+
+    // Move a layout made for the stage alone to the middle of a `columns` x
+    // `lines` terminal
+    fn center(&mut self, columns: u16, lines: u16) {
+        let dx = (columns - self.stage.width) / 2;
+        let dy = (lines - self.stage.height) / 2;
+        let shift =
+            |region: Region| Region::new(region.x + dx, region.y + dy, region.width, region.height);
+        self.columns = columns;
+        self.lines = lines;
+        self.stage = shift(self.stage);
+        self.lyrics = shift(self.lyrics);
+        self.credits = self.credits.map(shift);
+        self.art = self.art.map(shift);
+    }
+
+    // The whole terminal, where the frame and status go
+    pub fn screen(&self) -> Region {
+        Region::new(1, 1, self.columns, self.lines)
+    }
+
+    // The panes that get a border drawn around them
+    pub fn framed(&self) -> Vec<Region> {
+        std::iter::once(self.lyrics).chain(self.credits).collect()
+    }
+}
+
+// Columns and lines of a piece of art drawn `scale` times its size
+fn art_size(scale: u16) -> (u16, u16) {
+    (
+        ASCII_ART[0][0].len() as u16 * scale,
+        ASCII_ART[0].len() as u16 * scale,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fit(kind: Option<LayoutKind>, columns: u16, lines: u16) -> Option<TerminalLayout> {
+        let options = LayoutOptions {
+            kind,
+            ..LayoutOptions::default()
+        };
+        TerminalLayout::fit(options, columns, lines)
+    }
+
+    #[test]
+    fn picks_the_roomiest_layout_that_fits() {
+        let kind = |columns, lines| fit(None, columns, lines).map(|l| l.kind);
+        assert_eq!(kind(80, 24), Some(LayoutKind::Full));
+        assert_eq!(kind(50, 40), Some(LayoutKind::Stacked));
+        assert_eq!(kind(79, 24), Some(LayoutKind::Stacked));
+        assert_eq!(kind(30, 24), Some(LayoutKind::LyricsOnly));
+        assert_eq!(kind(10, 4), None);
+
+        // Forcing a layout that doesn't fit
+        assert!(fit(Some(LayoutKind::Full), 60, 40).is_none());
+        let forced = fit(Some(LayoutKind::LyricsOnly), 120, 40).unwrap();
+        assert_eq!(forced.options.kind, Some(LayoutKind::LyricsOnly));
+        assert_eq!(forced.credits, None);
+    }
+
+    #[test]
+    fn stacked_layout_keeps_the_art_only_with_room_for_it() {
+        let tall = fit(None, 50, 40).unwrap();
+        assert_eq!(tall.lyrics, Region::new(2, 2, 48, 13));
+        assert_eq!(tall.credits, Some(Region::new(2, 16, 48, 4)));
+        assert_eq!(tall.art, Some(Region::new(6, 21, 40, 20)));
+        // The art ends on the last line
+        let art = tall.art.unwrap();
+        assert_eq!(art.y + art.height - 1, 40);
+
+        let short = fit(None, 50, 20).unwrap();
+        assert_eq!(short.art, None);
+        assert_eq!(short.lyrics.height, 13);
+    }
+
+    #[test]
+    fn stage_is_the_classic_layout_centered() {
+        let options = LayoutOptions {
+            stage: Some((80, 24)),
+            ..LayoutOptions::default()
+        };
+        let staged = TerminalLayout::fit(options, 250, 60).unwrap();
+        let classic = TerminalLayout::full(80, 24);
+        assert_eq!(staged.stage, Region::new(86, 19, 80, 24));
+        assert_eq!(staged.lyrics, Region::new(87, 20, 35, 22));
+        assert_eq!(staged.art.unwrap().x, classic.art.unwrap().x + 85);
+
+        // Shrinks with a terminal smaller than the stage
+        let small = TerminalLayout::fit(options, 60, 20).unwrap();
+        assert_eq!(small.kind, LayoutKind::Stacked);
+        assert_eq!(small.stage, Region::new(1, 1, 60, 20));
+    }
+
+    #[test]
+    fn scaled_art_fits_under_the_credits() {
+        let options = LayoutOptions {
+            art_scale: 2,
+            ..LayoutOptions::default()
+        };
+        assert_eq!(LayoutKind::Full.min_size(2), (120, 44));
+        let narrow = TerminalLayout::fit(options, 119, 60).unwrap();
+        assert_eq!(narrow.kind, LayoutKind::Stacked);
+
+        let layout = TerminalLayout::fit(options, 120, 44).unwrap();
+        let art = layout.art.unwrap();
+        assert_eq!((art.width, art.height), (80, 40));
+        assert_eq!(art.x + art.width - 1, 117);
+        assert_eq!(art.y + art.height - 1, 44);
+        let credits = layout.credits.unwrap();
+        assert!(credits.x + credits.width <= art.x + art.width);
+    }
+}
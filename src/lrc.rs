@@ -0,0 +1,335 @@
+// lrc.rs
+// Import karaoke timings from LRC / enhanced LRC lyric files
+//
+//     [ti:Still Alive]                      ID tags, only `offset` is used
+//     [offset:+250]                         shift all times earlier by 250 ms
+//     [00:12.00]This was a triumph.         line typed until the next cue
+//     [00:20.00]<00:20.00>I'm <00:20.40>making <00:20.90>a <00:21.10>note <00:21.60>
+//                                           word tags time each word exactly
+//     [clear:00:40.00]                      clear the lyric page
+//     [credits:00:05.00]                    start the credits scroll
+//
+// Music starts with the first cue, and so do the credits unless a `credits`
+// directive says when. The show ends three seconds after the last cue.
+
+use crate::script::ScriptError;
+use crate::timeline::{Cue, Lyric};
+
+// Centiseconds the show keeps running after the last lyric
+const END_DELAY: u32 = 300;
+
+pub fn parse_lrc(source: &str) -> Result<Vec<Lyric>, ScriptError> {
+    let mut offset_ms = 0i64;
+    // Show time, source line and cue
+    let mut cues: Vec<(i64, usize, Lyric)> = Vec::new();
+    let mut credits = false;
+
+    for (index, raw) in source.lines().enumerate() {
+        let error = |message: String| ScriptError {
+            line: index + 1,
+            message,
+        };
+
+        let mut rest = raw.trim();
+        let mut times = Vec::new();
+
+        // Leading tags, either time tags or `[key:value]` ID tags
+        while let Some(tag_end) = rest.strip_prefix('[').and_then(|r| r.find(']')) {
+            let tag = &rest[1..=tag_end];
+            rest = &rest[tag_end + 2..];
+
+            if let Some(time) = parse_time(tag) {
+                times.push(time);
+                continue;
+            }
+
+            let (key, value) = tag.split_once(':').unwrap_or((tag, ""));
+            match key.trim() {
+                "offset" => {
+                    offset_ms = value
+                        .trim()
+                        .parse::<i64>()
+                        .map_err(|_| error(format!("invalid offset `{}`", value)))?;
+                }
+                "clear" => {
+                    let time = parse_time(value)
+                        .ok_or_else(|| error(format!("invalid clear time `{}`", value)))?;
+                    cues.push((time, index + 1, Lyric::new(Cue::ClearLyrics, 0, 0.0)));
+                }
+                "credits" => {
+                    let time = parse_time(value)
+                        .ok_or_else(|| error(format!("invalid credits time `{}`", value)))?;
+                    cues.push((time, index + 1, Lyric::new(Cue::StartCredits, 0, 0.0)));
+                    credits = true;
+                }
+                // A time tag gone wrong rather than an ID tag
+                key if !key.is_empty() && key.bytes().all(|b| b.is_ascii_digit()) => {
+                    return Err(error(format!("invalid time tag `[{}]`", tag)));
+                }
+                _ => {}
+            }
+        }
+
+        for &time in &times {
+            for (segment_time, segment) in parse_line(rest, time).map_err(error)? {
+                cues.push((segment_time, index + 1, segment));
+            }
+        }
+    }
+
+    // Repeated time tags can put lines out of order, keep file order on ties
+    cues.sort_by_key(|(time, _, _)| *time);
+
+    let mut lyrics = Vec::with_capacity(cues.len() + 3);
+    lyrics.push(Lyric::new(Cue::StartMusic, 0, 0.0));
+    if !credits {
+        lyrics.push(Lyric::new(Cue::StartCredits, 0, 0.0));
+    }
+    let mut last_line = 0;
+    for (time, line, mut lyric) in cues {
+        let too_large = || ScriptError {
+            line,
+            message: "time too large".to_string(),
+        };
+        let time = time.checked_sub(offset_ms / 10).ok_or_else(too_large)?;
+        lyric.time = u32::try_from(time.max(0)).map_err(|_| too_large())?;
+        lyrics.push(lyric);
+        last_line = line;
+    }
+    let end = lyrics
+        .last()
+        .map_or(0, |l| l.time)
+        .checked_add(END_DELAY)
+        .ok_or_else(|| ScriptError {
+            line: last_line,
+            message: "time too large".to_string(),
+        })?;
+    lyrics.push(Lyric::new(Cue::End, end, 0.0));
+
+    Ok(lyrics)
+}
+
+// Split a line into one cue per `<mm:ss.xx>` word tag, each typed exactly
+// until the following tag. Lines without word tags become a single cue
+// spread until the next cue.
+fn parse_line(text: &str, line_time: i64) -> Result<Vec<(i64, Lyric)>, String> {
+    let mut segments: Vec<(i64, String)> = vec![(line_time, String::new())];
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        let end = rest[start..]
+            .find('>')
+            .map(|e| start + e)
+            .ok_or_else(|| format!("unterminated word tag in `{}`", text))?;
+        let time = parse_time(&rest[start + 1..end])
+            .ok_or_else(|| format!("invalid word tag `{}`", &rest[start..=end]))?;
+
+        if let Some(last) = segments.last_mut() {
+            last.1.push_str(&rest[..start]);
+        }
+        segments.push((time, String::new()));
+        rest = &rest[end + 1..];
+    }
+    if let Some(last) = segments.last_mut() {
+        last.1.push_str(rest);
+    }
+
+    let mut cues = Vec::new();
+    for (i, (time, words)) in segments.iter().enumerate() {
+        // A tag with nothing after it only marks when the previous word ends
+        if words.is_empty() {
+            continue;
+        }
+        let interval = match segments.get(i + 1) {
+            Some((next_time, _)) => (next_time - time).max(0) as f32 / 100.0,
+            None => -1.0,
+        };
+        cues.push((
+            *time,
+            Lyric::new(
+                Cue::Line {
+                    text: words.to_string(),
+                    newline: false,
+                },
+                0,
+                interval,
+            ),
+        ));
+    }
+
+    // Blank timed lines still move to the next line
+    if cues.is_empty() {
+        cues.push((
+            line_time,
+            Lyric::new(
+                Cue::Line {
+                    text: String::new(),
+                    newline: true,
+                },
+                0,
+                -1.0,
+            ),
+        ));
+    } else if let Some((_, last)) = cues.last_mut() {
+        if let Cue::Line { newline, .. } = &mut last.cue {
+            *newline = true;
+        }
+    }
+
+    Ok(cues)
+}
+
+// `mm:ss`, `mm:ss.xx` or `mm:ss.xxx` to centiseconds
+fn parse_time(tag: &str) -> Option<i64> {
+    let (minutes, seconds) = tag.trim().split_once(':')?;
+    if minutes.is_empty() || !minutes.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let minutes = minutes.parse::<i64>().ok()?;
+    let (whole, fraction) = seconds.split_once('.').unwrap_or((seconds, "0"));
+    if whole.is_empty() || !whole.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let whole = whole.parse::<i64>().ok()?;
+    // Scale the fraction to two digits, so `.5`, `.50` and `.500` agree
+    let centis = format!("{:0<2}", fraction)[..2].parse::<i64>().ok()?;
+    minutes
+        .checked_mul(6000)?
+        .checked_add(whole.checked_mul(100)?)?
+        .checked_add(centis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str, newline: bool) -> Cue {
+        Cue::Line {
+            text: text.to_string(),
+            newline,
+        }
+    }
+
+    // (time, cue, interval) of every imported cue
+    fn import(source: &str) -> Vec<(u32, Cue, f32)> {
+        parse_lrc(source)
+            .unwrap()
+            .into_iter()
+            .map(|lyric| (lyric.time, lyric.cue, lyric.interval))
+            .collect()
+    }
+
+    #[test]
+    fn lines_start_the_music_and_credits_and_end_later() {
+        assert_eq!(
+            import("[ti:Still Alive]\n[00:12.00]This was a triumph.\n"),
+            [
+                (0, Cue::StartMusic, 0.0),
+                (0, Cue::StartCredits, 0.0),
+                (1200, line("This was a triumph.", true), -1.0),
+                (1500, Cue::End, 0.0),
+            ]
+        );
+        // Unless the file says when the credits start
+        let lyrics = import("[credits:00:05.5]\n[00:12.00]Hi\n");
+        assert_eq!(lyrics[1], (550, Cue::StartCredits, 0.0));
+        assert_eq!(lyrics.len(), 4);
+    }
+
+    #[test]
+    fn offset_moves_every_cue_earlier() {
+        let lyrics = import("[offset:+250]\n[00:01.00]Hi\n[clear:00:02.00]\n");
+        assert_eq!(lyrics[2], (75, line("Hi", true), -1.0));
+        assert_eq!(lyrics[3], (175, Cue::ClearLyrics, 0.0));
+        // Negative offsets delay, nothing goes before the start
+        let lyrics = import("[offset:-500]\n[00:01.00]Hi\n");
+        assert_eq!(lyrics[2].0, 150);
+        let lyrics = import("[offset:2000]\n[00:01.00]Hi\n");
+        assert_eq!(lyrics[2].0, 0);
+    }
+
+    #[test]
+    fn word_tags_time_each_word() {
+        assert_eq!(
+            import("[00:20.00]<00:20.00>I'm <00:20.40>making <00:20.90>a <00:21.10>\n")[2..5],
+            [
+                (2000, line("I'm ", false), 0.4),
+                (2040, line("making ", false), 0.5),
+                (2090, line("a ", true), 0.2),
+            ]
+        );
+    }
+
+    #[test]
+    fn clear_directive_clears_the_page() {
+        let lyrics = import("[00:01.00]Hi\n[clear:00:02.50]\n[00:03.00]There\n");
+        assert_eq!(lyrics[3], (250, Cue::ClearLyrics, 0.0));
+        assert_eq!(lyrics[4].0, 300);
+    }
+
+    #[test]
+    fn repeated_time_tags_repeat_the_line_in_order() {
+        let lyrics = import("[00:10.00][00:30.00]Chorus\n[00:20.00]Verse\n");
+        assert_eq!(
+            lyrics[2..5],
+            [
+                (1000, line("Chorus", true), -1.0),
+                (2000, line("Verse", true), -1.0),
+                (3000, line("Chorus", true), -1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_timestamps() {
+        for tag in [
+            "1:2x", "00:", ":30", "00:30.", "00:3a.00", "-1:00", "00:30.x",
+        ] {
+            assert_eq!(parse_time(tag), None, "{}", tag);
+        }
+        assert_eq!(parse_time("1:02.5"), Some(6250));
+        assert_eq!(parse_time("00:02.505"), Some(250));
+
+        let error = |source: &str| parse_lrc(source).err().unwrap().to_string();
+        assert_eq!(
+            error("[00:01.00]Hi\n[00:0x.00]There\n"),
+            "line 2: invalid time tag `[00:0x.00]`"
+        );
+        assert_eq!(
+            error("[00:01.00]<00:01.00>Hi <00:1x.00>there\n"),
+            "line 1: invalid word tag `<00:1x.00>`"
+        );
+        assert_eq!(
+            error("[00:01.00]<00:01.00>Hi <00:02.00\n"),
+            "line 1: unterminated word tag in `<00:01.00>Hi <00:02.00`"
+        );
+        assert_eq!(error("[clear:soon]\n"), "line 1: invalid clear time `soon`");
+        assert_eq!(error("[offset:later]\n"), "line 1: invalid offset `later`");
+    }
+
+    #[test]
+    fn times_past_the_longest_show() {
+        let error = |source: &str| parse_lrc(source).err().unwrap().to_string();
+        // Too big to even count in centiseconds
+        assert_eq!(parse_time("9999999999999999:00.00"), None);
+        assert_eq!(
+            error("[9999999999999999:00.00]Hi\n"),
+            "line 1: invalid time tag `[9999999999999999:00.00]`"
+        );
+        // Too late for a show, rather than wrapping around to early on
+        assert_eq!(
+            error("[00:01.00]Hi\n[800000:00.00]There\n"),
+            "line 2: time too large"
+        );
+        assert_eq!(
+            error("[00:01.00]Hi\n[offset:-9223372036854775808]\n"),
+            "line 1: time too large"
+        );
+        // The last cue fits, the end of the show after it doesn't
+        assert_eq!(error("[715827:52.95]Hi\n"), "line 1: time too large");
+        assert_eq!(import("[715827:49.95]Hi\n")[3].0, u32::MAX);
+    }
+}
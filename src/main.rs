@@ -1,153 +1,449 @@
-use rodio::Decoder;
-use rodio::OutputStream;
-use rodio::Sink;
 use std::env;
-use std::io::Cursor;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+mod audio;
+mod cast;
+mod check;
+mod cli;
+mod clock;
+mod compositor;
+mod controls;
 mod data;
+mod effects;
+mod font;
+mod html;
+mod layout;
+mod lrc;
+mod raster;
+mod render;
+mod renderer;
+mod script;
+mod serial;
+mod show;
+mod snapshot;
+mod svg;
+mod term;
+mod theme;
+mod timeline;
 mod tui_draw;
+mod virtual_screen;
 
+use audio::{
+    load_music, open_output, read_music, start_sync, write_wav, AudioError, AudioOutput, MusicData,
+    SharedMusic,
+};
+use cast::{Cast, Recording};
+use check::{check_timeline, Severity};
+use cli::{Options, RenderFormat};
+use clock::{RealClock, SharedClock};
+use controls::start_controls;
 use data::get_lyrics;
-use tui_draw::{
-    begin_draw, clear_lyrics, clear_screen, draw_ascii_art, draw_frame, draw_lyrics, end_draw,
-    move_cursor, start_credits, TerminalLayout,
+use effects::Effects;
+use html::export_html;
+use layout::{TerminalLayout, MIN_COLUMNS, MIN_LINES};
+use raster::ImageFormat;
+use render::{render_cast, render_frames, render_svg};
+use renderer::{CrosstermRenderer, Renderer};
+use script::load_script;
+use serial::SerialOutput;
+use show::{run_show, AUDIO_UNAVAILABLE};
+use snapshot::dump_frame;
+use term::TermProfile;
+use theme::{
+    default_theme_file, find_theme, load_theme_file, ColorDepth, Palette, Theme, ThemeFile,
 };
-
-const MP3_CONSTANT: &[u8] = include_bytes!("../music/ending.mp3");
+use timeline::{Cue, Lyric};
+use tui_draw::{end_draw, start_effects, Screen, ScreenHandle};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.contains(&"-V".to_string()) || args.contains(&"--version".to_string()) {
+    let options = match Options::parse(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    if options.show_version {
         println!("Portal_StillAlive_Rust 0.9.8");
+        if cfg!(not(feature = "audio")) {
+            println!("Sound support: compiled out");
+        }
+        return;
+    }
+
+    // Load the show before touching the terminal so script errors stay readable
+    let lyrics = match &options.script {
+        Some(path) => match load_script(path) {
+            Ok(lyrics) => lyrics,
+            Err(e) => {
+                eprintln!("Error loading script {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => get_lyrics(),
+    };
+
+    if options.check {
+        // Lint against the layout the show would play in, 80x24 unless told
+        let size = options.size.unwrap_or((MIN_COLUMNS, MIN_LINES));
+        let layout = TerminalLayout::new(Some(size), options.layout);
+        let problems = check_timeline(&lyrics, &layout);
+        for problem in &problems {
+            println!("{}", problem);
+        }
+        let errors = problems
+            .iter()
+            .filter(|p| p.severity == Severity::Error)
+            .count();
+        println!(
+            "{} cues checked: {} errors, {} warnings",
+            lyrics.len(),
+            errors,
+            problems.len() - errors
+        );
+        std::process::exit(if errors > 0 { 1 } else { 0 });
+    }
+
+    if let Some(time) = options.dump_frame {
+        let size = options.size.unwrap_or((MIN_COLUMNS, MIN_LINES));
+        let layout = TerminalLayout::new(Some(size), options.layout);
+        match dump_frame(&lyrics, &layout, time) {
+            Ok(frame) => print!("{}", frame),
+            Err(e) => {
+                eprintln!("Error drawing frame: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // Themes from the theme file, the one in the config directory only when
+    // there is one
+    let theme_file = match options
+        .theme_file
+        .clone()
+        .or_else(|| default_theme_file().filter(|path| path.exists()))
+    {
+        Some(path) => match load_theme_file(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!("Error loading themes {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => ThemeFile::default(),
+    };
+    let theme = match find_theme(options.theme.as_deref(), &theme_file) {
+        Ok(theme) => theme,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    if options.render {
+        let size = options.size.unwrap_or((MIN_COLUMNS, MIN_LINES));
+        let layout = TerminalLayout::new(Some(size), options.layout);
+        let profile =
+            TermProfile::from_name(options.term_profile.as_deref().unwrap_or("xterm-256color"));
+        let palette = Palette::new(&theme, ColorDepth::for_profile(&profile));
+        let rendered = match options.format {
+            RenderFormat::Cast => render_cast(
+                &lyrics,
+                &layout,
+                profile,
+                palette,
+                output_file(options.output.as_deref()),
+            ),
+            RenderFormat::Svg => render_svg(
+                &lyrics,
+                &layout,
+                &theme,
+                output_file(options.output.as_deref()),
+            ),
+            RenderFormat::Frames(format) => save_frames(&options, &lyrics, &layout, &theme, format),
+        };
+        if let Err(e) = rendered {
+            eprintln!("Error rendering the show: {}", e);
+            std::process::exit(1);
+        }
         return;
     }
 
-    let mut no_music = false;
-    if args.contains(&"-M".to_string()) || args.contains(&"--mute-music".to_string()) {
-        no_music = true;
+    if options.export {
+        // The page plays the file itself, it only has to be read
+        let music = if options.no_music {
+            None
+        } else {
+            match read_music(options.music.as_deref()) {
+                Ok(Some(data)) => Some(data),
+                Ok(None) => {
+                    eprintln!("Note: this build has no embedded music, exporting without music");
+                    None
+                }
+                Err(e) => {
+                    eprintln!("Error loading music: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        };
+        let size = options.size.unwrap_or((MIN_COLUMNS, MIN_LINES));
+        let layout = TerminalLayout::new(Some(size), options.layout);
+        let out = output_file(options.output.as_deref());
+        if let Err(e) = export_html(out, &lyrics, &layout, &theme, music.as_deref()) {
+            eprintln!("Error exporting the show: {}", e);
+            std::process::exit(1);
+        }
+        return;
     }
-    ctrlc::set_handler(|| {
-        let _ = tui_draw::end_draw();
+
+    // Shown in the frame when the music was wanted but can't play
+    let mut audio_status = None;
+    let music_data = if options.no_music {
+        None
+    } else {
+        match load_music(options.music.as_deref()) {
+            Ok(data) => Some(data),
+            // Still worth watching without sound
+            #[cfg(not(feature = "audio"))]
+            Err(e @ audio::MusicError::CompiledOut) => {
+                eprintln!("Note: {}, playing without music", e);
+                audio_status = Some(AUDIO_UNAVAILABLE);
+                None
+            }
+            Err(e) => {
+                eprintln!("Error loading music: {}", e);
+                std::process::exit(1);
+            }
+        }
+    };
+
+    // Open the sound device now, a missing one is easier to deal with here
+    // than once the show covers the terminal
+    let audio: Option<(AudioOutput, MusicData)> = match music_data {
+        Some(data) => match open_output() {
+            Ok(output) => Some((output, data)),
+            Err(e) => {
+                if !options.continue_muted && !confirm_muted(&e) {
+                    std::process::exit(1);
+                }
+                audio_status = Some(AUDIO_UNAVAILABLE);
+                None
+            }
+        },
+        None => None,
+    };
+
+    // Initialize terminal and layout
+    let profile = match &options.term_profile {
+        Some(name) => TermProfile::from_name(name),
+        // $TERM describes our own terminal, not the one on the line
+        None if options.serial.is_some() => TermProfile::from_name("vt220"),
+        None => TermProfile::detect(),
+    };
+    // Hardware terminals can't tell their size, ours is asked
+    let size = match options.size {
+        Some(size) => Some(size),
+        None if options.serial.is_some() || !profile.query_size => Some((MIN_COLUMNS, MIN_LINES)),
+        None => None,
+    };
+    let layout = TerminalLayout::new(size, options.layout);
+    // The environment only describes our own terminal
+    let depth = if options.serial.is_some() {
+        ColorDepth::for_profile(&profile)
+    } else {
+        ColorDepth::detect(&profile)
+    };
+    let palette = Palette::new(&theme, depth);
+    // The effects need shades of the theme's colors and a cursor to place
+    let crt = options.crt && profile.cursor_addressing && palette.has_shades();
+    // Record what goes to the terminal, as big as the show was laid out
+    let record = options.record.as_ref().map(|path| {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|time| time.as_secs());
+        let cast = File::create(path).and_then(|file| {
+            let out = BufWriter::new(file);
+            Cast::new(out, layout.columns, layout.lines, &profile.name, timestamp)
+        });
+        cast.unwrap_or_else(|e| {
+            eprintln!("Error creating {}: {}", path.display(), e);
+            std::process::exit(1);
+        })
+    });
+    let renderer = match &options.serial {
+        Some(path) => match SerialOutput::open(path, options.baud) {
+            Ok(port) => renderer_for(port, record),
+            Err(e) => {
+                eprintln!("Error opening {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => renderer_for(io::stdout(), record),
+    };
+    // Only our own terminal's window can be resized under the show
+    let follow_resize = size.is_none();
+    let screen = ScreenHandle::spawn(Screen::new(
+        renderer,
+        profile,
+        palette,
+        crt.then(Effects::new),
+        &layout,
+    ));
+
+    let interrupted = screen.clone();
+    ctrlc::set_handler(move || {
+        let _ = end_draw(&interrupted);
         println!("Interrupt by user");
         std::process::exit(0);
     })
     .expect("Unable to exit with ctrl+C pressed!");
 
-    // Initialize terminal and layout
-    let layout = TerminalLayout::new();
-
     // Begin drawing setup
-    if let Err(e) = begin_draw() {
+    if let Some(Err(e)) = screen.draw_wait(|screen| screen.begin_draw()) {
         eprintln!("Error setting up terminal: {}", e);
         return;
     }
 
     // Clear screen
-    if let Err(e) = clear_screen() {
+    if let Some(Err(e)) = screen.draw_wait(|screen| screen.clear_screen()) {
         eprintln!("Error clearing screen: {}", e);
         return;
     }
 
     // Draw frame
-    if let Err(e) = draw_frame(&layout) {
+    if let Some(Err(e)) = screen.draw_wait(|screen| screen.draw_frame()) {
         eprintln!("Error drawing frame: {}", e);
         return;
     }
+    if let Some(status) = audio_status {
+        screen.draw(move |screen| screen.draw_status(status));
+    }
+    thread::sleep(Duration::from_secs(1));
 
     // Main lyrics processing loop
-    let lyrics = get_lyrics();
-    let start_time = Instant::now();
-    let mut current_lyric = 0;
-    let mut x = 0u16;
-    let mut y = 0u16;
-
-    //Print out lyric line-by-line
-    while current_lyric < lyrics.len() && lyrics[current_lyric].mode != 9 {
-        let current_time = start_time.elapsed().as_millis() as u32 / 10;
-        //Each line of lyric have it own "timestamp", line will start printing again when "current_time" pass it
-        if current_time > lyrics[current_lyric].time {
-            let lyric = &lyrics[current_lyric];
-
-            // Calculate interval for each character
-            let word_count = if lyric.mode <= 1 || lyric.mode >= 5 {
-                std::cmp::max(lyric.words.len(), 1)
-            } else {
-                1
-            };
-
-            let interval = if lyric.interval < 0.0 {
-                if current_lyric + 1 < lyrics.len() {
-                    (lyrics[current_lyric + 1].time - lyric.time) as f32 / 100.0 / word_count as f32
-                } else {
-                    0.1
-                }
-            } else {
-                lyric.interval / word_count as f32
-            };
-
-            match lyric.mode {
-                0 => {
-                    // Lyric with newline
-                    if let Ok(new_x) = draw_lyrics(&lyric.words, x, y, interval, true) {
-                        x = new_x;
-                        y += 1;
-                    }
-                }
-                1 => {
-                    // Lyric without newline
-                    if let Ok(new_x) = draw_lyrics(&lyric.words, x, y, interval, false) {
-                        x = new_x;
-                    }
-                }
-                2 => {
-                    // ASCII art
-                    if let Ok(art_index) = lyric.words.parse::<usize>() {
-                        let _ = draw_ascii_art(&layout, art_index);
-                        let _ = move_cursor(x + 2, y + 2);
-                    }
-                }
-                3 => {
-                    // Clear lyrics
-                    let _ = clear_lyrics(&layout);
-                    x = 0;
-                    y = 0;
-                }
-                4 => {
-                    if !no_music {
-                        start_wonderful_music(MP3_CONSTANT);
-                    }
-                }
-                5 => {
-                    start_credits(layout.clone());
-                }
-                _ => {}
-            }
-
-            current_lyric += 1;
-        }
-
-        std::thread::sleep(Duration::from_millis(1));
+    let real_clock = Arc::new(RealClock::new());
+    let clock: SharedClock = real_clock.clone();
+    let music: SharedMusic = Arc::new(Mutex::new(None));
+    start_controls(
+        screen.clone(),
+        clock.clone(),
+        music.clone(),
+        &lyrics,
+        follow_resize,
+    );
+    if crt {
+        start_effects(screen.clone(), clock.clone());
     }
+    // Without music there's nothing better than the wall clock to follow
+    if audio.is_some() {
+        start_sync(real_clock, music.clone(), options.audio_offset);
+    }
+
+    let music_failure = run_show(&screen, &clock, &lyrics, audio.as_ref(), &music);
 
     // Wait a bit before cleanup
-    std::thread::sleep(Duration::from_secs(2));
+    clock.sleep(Duration::from_secs(2));
 
     // Cleanup
-    if let Err(e) = end_draw() {
+    if let Err(e) = end_draw(&screen) {
         eprintln!("Error cleaning up terminal: {}", e);
     }
+    if let Some(e) = music_failure {
+        eprintln!("The music couldn't play: {}", e);
+    }
+}
 
-    fn start_wonderful_music(mp3_data: &'static [u8]) {
-        thread::spawn(move || {
-            let (_stream, stream_handle) = OutputStream::try_default().expect("No output device");
-            Sink::try_new(&stream_handle).expect("Failed to create Sink1");
-            let sink = Sink::try_new(&stream_handle).expect("Failed to create Sink2");
-            let cursor = Cursor::new(mp3_data);
-            let source = Decoder::new(cursor).expect("Failed to decode MP3 data");
-            sink.append(source);
-            sink.sleep_until_end(); // Keep the thread alive while music play
-        });
+// Write to the file at `path`, or stdout without one
+fn output_file(path: Option<&Path>) -> Box<dyn Write> {
+    match path {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(e) => {
+                eprintln!("Error creating {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => Box::new(io::stdout().lock()),
+    }
+}
+
+// `render --frames`: a numbered picture of every frame in the directory,
+// and the music as music.wav next to them
+fn save_frames(
+    options: &Options,
+    lyrics: &[Lyric],
+    layout: &TerminalLayout,
+    theme: &Theme,
+    format: ImageFormat,
+) -> io::Result<()> {
+    let dir = options.frames.as_deref().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    render_frames(
+        lyrics,
+        layout,
+        theme,
+        options.fps,
+        format,
+        |number, image| {
+            let name = format!("frame-{:06}.{}", number, format.extension());
+            fs::write(dir.join(name), image)
+        },
+    )?;
+
+    if options.no_music {
+        return Ok(());
+    }
+    let Some(start) = lyrics.iter().find(|lyric| lyric.cue == Cue::StartMusic) else {
+        eprintln!("Note: the show never starts the music, no music.wav written");
+        return Ok(());
+    };
+    let data = match load_music(options.music.as_deref()) {
+        Ok(data) => data,
+        #[cfg(not(feature = "audio"))]
+        Err(e @ audio::MusicError::CompiledOut) => {
+            eprintln!("Note: {}, no music.wav written", e);
+            return Ok(());
+        }
+        Err(e) => return Err(io::Error::other(e.to_string())),
+    };
+    let lead = Duration::from_millis(start.time as u64 * 10);
+    let mut wav = Vec::new();
+    write_wav(&data, lead, &mut wav)?;
+    fs::write(dir.join("music.wav"), wav)
+}
+
+// Draw on `out`, and record it too when asked to
+fn renderer_for<W: Write + Send + 'static>(
+    out: W,
+    record: Option<Cast<BufWriter<File>>>,
+) -> Box<dyn Renderer> {
+    match record {
+        Some(cast) => Box::new(CrosstermRenderer::new(Recording::new(out, cast))),
+        None => Box::new(CrosstermRenderer::new(out)),
+    }
+}
+
+// Ask whether to play on without music, only when someone can answer
+fn confirm_muted(error: &AudioError) -> bool {
+    eprintln!("Audio is unavailable: {}", error);
+    if !io::stdin().is_terminal() {
+        eprintln!("Pass --continue-muted to play the show without music");
+        return false;
+    }
+
+    eprint!("Continue without music? [Y/n] ");
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
     }
+    !answer.trim().to_lowercase().starts_with('n')
 }
@@ -0,0 +1,336 @@
+// raster.rs
+// Screens turned into pictures, for making a video of the show: every cell
+// is drawn with the bitmap font at a fixed size and the picture saved as a
+// PPM or a PNG, with a small deflate encoder of its own for the latter.
+
+use crossterm::style::Color;
+use std::io::{self, Write};
+
+use crate::font;
+use crate::theme::Rgb;
+
+// A cell is CELL_WIDTH x CELL_HEIGHT font pixels with the glyph TOP rows
+// down, every font pixel a SCALE x SCALE block in the picture
+const CELL_WIDTH: usize = font::WIDTH + 1;
+const CELL_HEIGHT: usize = 12;
+const TOP: usize = 2;
+const SCALE: usize = 2;
+// Text drawn without a theme color
+const DEFAULT_FOREGROUND: Rgb = (192, 192, 192);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Ppm,
+}
+
+impl ImageFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Ppm => "ppm",
+        }
+    }
+}
+
+// 8-bit RGB pixels, row by row
+pub struct Picture {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Picture {
+    // The screen showing `cells` on `background`
+    pub fn of_cells(cells: &[Vec<(char, Option<Color>)>], background: Rgb) -> Self {
+        let columns = cells.first().map_or(0, |row| row.len());
+        let width = columns * CELL_WIDTH * SCALE;
+        let height = cells.len() * CELL_HEIGHT * SCALE;
+        let mut pixels = [background.0, background.1, background.2].repeat(width * height);
+        for (row, line) in cells.iter().enumerate() {
+            for (column, &(ch, color)) in line.iter().enumerate().filter(|(_, (ch, _))| *ch != ' ')
+            {
+                let (r, g, b) = rgb(color);
+                // A SCALE x SCALE block for every lit font pixel
+                let block = [r, g, b].repeat(SCALE);
+                for y in 0..CELL_HEIGHT {
+                    for x in (0..CELL_WIDTH).filter(|&x| lit(ch, x, y)) {
+                        let px = (column * CELL_WIDTH + x) * SCALE;
+                        for py in
+                            (row * CELL_HEIGHT + y) * SCALE..(row * CELL_HEIGHT + y + 1) * SCALE
+                        {
+                            let at = (py * width + px) * 3;
+                            pixels[at..at + block.len()].copy_from_slice(&block);
+                        }
+                    }
+                }
+            }
+        }
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn write<W: Write>(&self, mut out: W, format: ImageFormat) -> io::Result<()> {
+        match format {
+            ImageFormat::Ppm => {
+                write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+                out.write_all(&self.pixels)?;
+            }
+            ImageFormat::Png => {
+                out.write_all(b"\x89PNG\r\n\x1a\n")?;
+                let mut header = Vec::new();
+                header.extend_from_slice(&(self.width as u32).to_be_bytes());
+                header.extend_from_slice(&(self.height as u32).to_be_bytes());
+                // 8 bits a channel, RGB, no interlacing
+                header.extend_from_slice(&[8, 2, 0, 0, 0]);
+                png_chunk(&mut out, b"IHDR", &header)?;
+                // Every row starts with its filter, none here
+                let mut rows = Vec::with_capacity((self.width * 3 + 1) * self.height);
+                for row in self.pixels.chunks(self.width * 3) {
+                    rows.push(0);
+                    rows.extend_from_slice(row);
+                }
+                png_chunk(&mut out, b"IDAT", &zlib(&rows))?;
+                png_chunk(&mut out, b"IEND", &[])?;
+            }
+        }
+        out.flush()
+    }
+}
+
+// Whether font pixel (`x`, `y`) of a cell showing `ch` is lit
+fn lit(ch: char, x: usize, y: usize) -> bool {
+    if ch == '█' {
+        return true;
+    }
+    if x >= font::WIDTH || !(TOP..TOP + font::HEIGHT).contains(&y) {
+        return false;
+    }
+    let y = y - TOP;
+    // A box for anything the font doesn't have
+    font::lit(ch, x, y).unwrap_or(y < 7 && (x == 0 || x == font::WIDTH - 1 || y == 0 || y == 6))
+}
+
+// Colors come from a 24-bit palette, anything else is drawn in the default
+fn rgb(color: Option<Color>) -> Rgb {
+    match color {
+        Some(Color::Rgb { r, g, b }) => (r, g, b),
+        _ => DEFAULT_FOREGROUND,
+    }
+}
+
+fn png_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    out.write_all(&crc32(&[kind, data]).to_be_bytes())
+}
+
+// A byte at a time, from a table of what every byte value does
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut crc = n as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+        *entry = crc;
+    }
+    let mut crc = !0u32;
+    for &byte in parts.iter().flat_map(|part| part.iter()) {
+        crc = table[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+// Deflate's length and distance codes: the smallest value of each, and how
+// many extra bits say how far above it a value is
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const WINDOW: usize = 32768;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+// Repeats longer than this are not looked into for the next ones, the long
+// runs of a picture would cost a lookup for every byte otherwise
+const MAX_INSERT: usize = 16;
+// Bytes the Adler-32 sums can take before they need reducing
+const ADLER_BLOCK: usize = 5552;
+
+// `data` as a zlib stream: one deflate block with the fixed codes, repeats
+// found through a table of where every three bytes were last seen. Pictures
+// of a text screen are mostly runs and repeated rows, that's plenty.
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut bits = Bits {
+        out: vec![0x78, 0x01],
+        buffer: 0,
+        count: 0,
+    };
+    // The last block, fixed codes
+    bits.put(1, 1);
+    bits.put(1, 2);
+
+    let hash = |i: usize| {
+        let three = ((data[i] as u32) << 16) | ((data[i + 1] as u32) << 8) | data[i + 2] as u32;
+        (three.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+    };
+    let mut seen = vec![usize::MAX; 1 << HASH_BITS];
+    let mut i = 0;
+    while i < data.len() {
+        let mut length = 0;
+        if i + 3 <= data.len() {
+            let h = hash(i);
+            let candidate = seen[h];
+            seen[h] = i;
+            if candidate != usize::MAX && i - candidate <= WINDOW {
+                // A word at a time, then the bytes left
+                let limit = MAX_MATCH.min(data.len() - i);
+                let same = |at: usize, n: usize| {
+                    data[candidate + at..candidate + at + n] == data[i + at..i + at + n]
+                };
+                while length + 8 <= limit && same(length, 8) {
+                    length += 8;
+                }
+                while length < limit && same(length, 1) {
+                    length += 1;
+                }
+                if length >= 3 {
+                    bits.copy(length, i - candidate);
+                }
+            }
+        }
+        if length >= 3 {
+            if length <= MAX_INSERT {
+                for j in i + 1..(i + length).min(data.len() - 2) {
+                    seen[hash(j)] = j;
+                }
+            }
+            i += length;
+        } else {
+            bits.literal(data[i] as u32);
+            i += 1;
+        }
+    }
+    bits.literal(256);
+
+    let mut out = bits.finish();
+    let (mut a, mut b) = (1u32, 0u32);
+    for block in data.chunks(ADLER_BLOCK) {
+        for &byte in block {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    out.extend_from_slice(&((b << 16) | a).to_be_bytes());
+    out
+}
+
+// Deflate output, packed from the lowest bit of every byte up
+struct Bits {
+    out: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl Bits {
+    fn put(&mut self, value: u32, count: u32) {
+        self.buffer |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // Huffman codes go highest bit first
+    fn put_code(&mut self, code: u32, length: u32) {
+        self.put(code.reverse_bits() >> (32 - length), length);
+    }
+
+    fn literal(&mut self, value: u32) {
+        match value {
+            0..=143 => self.put_code(0x30 + value, 8),
+            144..=255 => self.put_code(0x190 + value - 144, 9),
+            256..=279 => self.put_code(value - 256, 7),
+            _ => self.put_code(0xc0 + value - 280, 8),
+        }
+    }
+
+    // Repeat `length` bytes from `distance` back
+    fn copy(&mut self, length: usize, distance: usize) {
+        let i = LENGTH_BASE.partition_point(|&base| base as usize <= length) - 1;
+        self.literal(257 + i as u32);
+        self.put(
+            (length - LENGTH_BASE[i] as usize) as u32,
+            LENGTH_EXTRA[i] as u32,
+        );
+        let i = DISTANCE_BASE.partition_point(|&base| base as usize <= distance) - 1;
+        self.put_code(i as u32, 5);
+        self.put(
+            (distance - DISTANCE_BASE[i] as usize) as u32,
+            DISTANCE_EXTRA[i] as u32,
+        );
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.buffer as u8);
+        }
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_become_pictures() {
+        let amber = Some(Color::Rgb {
+            r: 255,
+            g: 176,
+            b: 0,
+        });
+        let picture = Picture::of_cells(&[vec![('I', amber), (' ', None)]], (0, 0, 0));
+        let mut ppm = Vec::new();
+        picture.write(&mut ppm, ImageFormat::Ppm).unwrap();
+
+        assert!(ppm.starts_with(b"P6\n24 24\n255\n"));
+        let pixel = |x: usize, y: usize| {
+            let at = b"P6\n24 24\n255\n".len() + (y * 24 + x) * 3;
+            &ppm[at..at + 3]
+        };
+        // The top of the I, a font pixel being 2x2 picture pixels
+        assert_eq!(pixel(0, TOP * SCALE), [0, 0, 0]);
+        assert_eq!(pixel(2, TOP * SCALE), [255, 176, 0]);
+        assert_eq!(pixel(3, TOP * SCALE + 1), [255, 176, 0]);
+        assert_eq!(pixel(2, 0), [0, 0, 0]);
+
+        let mut png = Vec::new();
+        picture.write(&mut png, ImageFormat::Png).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x18\0\0\0\x18"));
+        assert!(png.ends_with(b"\0\0\0\0IEND\xae\x42\x60\x82"));
+    }
+}
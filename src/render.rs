@@ -0,0 +1,300 @@
+// render.rs
+// The live show played on a clock stepped a centisecond at a time, for
+// recordings made without a terminal, a sound device or waiting through the
+// show in real time
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::cast::Cast;
+use crate::clock::{Clock, ManualClock, SharedClock};
+use crate::compositor::Region;
+use crate::layout::TerminalLayout;
+use crate::raster::{ImageFormat, Picture};
+use crate::renderer::CrosstermRenderer;
+use crate::show::run_show;
+use crate::svg::SvgAnimation;
+use crate::term::TermProfile;
+use crate::theme::{ColorDepth, Palette, Rgb, Theme};
+use crate::timeline::{Cue, Lyric};
+use crate::tui_draw::{end_draw, Screen, ScreenHandle};
+use crate::virtual_screen::VirtualScreen;
+
+// How far the virtual clock moves between looks at the screen, in
+// centiseconds, finer than any typing
+const STEP: u32 = 1;
+
+// The live show stays on its last frame this long (centiseconds)
+const END_HOLD: u32 = 200;
+
+// No more pictures a second than the clock has steps
+pub const MAX_FPS: u32 = 100 / STEP;
+
+// Show time the show is over at, in centiseconds
+pub fn show_end(lyrics: &[Lyric]) -> u32 {
+    let end = lyrics
+        .iter()
+        .find(|lyric| lyric.cue == Cue::End)
+        .or(lyrics.last())
+        .map_or(0, |lyric| lyric.time);
+    end + END_HOLD
+}
+
+// Everything written to it, kept until taken
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Captured {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Play the show on `screen` the way it runs live, calling `frame` with the
+// show time (centiseconds) after every step, once everything due by then is
+// drawn. Every thread of the show holds the clock, so it only moves on when
+// they are all waiting for a later time.
+pub fn play<F>(screen: Screen, lyrics: &[Lyric], mut frame: F) -> io::Result<()>
+where
+    F: FnMut(u32) -> io::Result<()>,
+{
+    let screen = ScreenHandle::spawn(screen);
+    let framed = screen.draw_wait(|screen| {
+        screen.begin_draw()?;
+        screen.clear_screen()?;
+        screen.draw_frame()
+    });
+    framed.unwrap_or(Ok(()))?;
+
+    let end = show_end(lyrics);
+    let clock = Arc::new(ManualClock::new());
+    thread::scope(|scope| {
+        let show_clock: SharedClock = clock.clone();
+        let show_screen = screen.clone();
+        let show = scope.spawn(move || {
+            let music = Arc::new(Mutex::new(None));
+            run_show(&show_screen, &show_clock, lyrics, None, &music)
+        });
+
+        let mut result = Ok(());
+        let mut time = 0;
+        // A line still typed at the end keeps the show going a little longer
+        while time <= end || !show.is_finished() {
+            clock.set(centis(time));
+            clock.wait_idle();
+            if time <= end && result.is_ok() {
+                let presented = screen.draw_wait(|screen| screen.present());
+                result = presented.unwrap_or(Ok(())).and_then(|_| frame(time));
+            }
+            time += STEP;
+        }
+        // The credits scroll until drawing ends, a seek wakes them to notice
+        let _ = end_draw(&screen);
+        clock.seek(centis(time));
+        result
+    })
+}
+
+fn centis(time: u32) -> Duration {
+    Duration::from_millis(time as u64 * 10)
+}
+
+// The whole show as an asciinema recording of a `profile` terminal
+pub fn render_cast<W: Write>(
+    lyrics: &[Lyric],
+    layout: &TerminalLayout,
+    profile: TermProfile,
+    palette: Palette,
+    out: W,
+) -> io::Result<()> {
+    if !profile.cursor_addressing {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("a {} terminal can't be rendered to", profile.name),
+        ));
+    }
+    let mut cast = Cast::new(out, layout.columns, layout.lines, &profile.name, None)?;
+    let output = Captured::default();
+    let renderer = CrosstermRenderer::new(output.clone());
+    let screen = Screen::new(Box::new(renderer), profile, palette, None, layout);
+    play(screen, lyrics, |time| {
+        let bytes = output.take();
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        cast.output(centis(time), &bytes)
+    })?;
+    // Leaving the alternate screen and the colors, written once the show ended
+    let bytes = output.take();
+    if !bytes.is_empty() {
+        cast.output(centis(show_end(lyrics)), &bytes)?;
+    }
+    cast.flush()
+}
+
+// A screen that keeps what's drawn on it in `theme`'s colors
+fn virtual_show(layout: &TerminalLayout, theme: &Theme) -> (VirtualScreen, Screen) {
+    let virtual_screen = VirtualScreen::new(layout.columns, layout.lines);
+    let screen = Screen::new(
+        Box::new(virtual_screen.clone()),
+        TermProfile::from_name("xterm"),
+        Palette::new(theme, ColorDepth::TrueColor),
+        None,
+        layout,
+    );
+    (virtual_screen, screen)
+}
+
+fn background(theme: &Theme) -> Rgb {
+    theme.colors.map_or((0, 0, 0), |colors| colors.background)
+}
+
+// The whole show as an animated SVG in `theme`'s colors
+pub fn render_svg<W: Write>(
+    lyrics: &[Lyric],
+    layout: &TerminalLayout,
+    theme: &Theme,
+    out: W,
+) -> io::Result<()> {
+    let (virtual_screen, screen) = virtual_show(layout, theme);
+    let panes: Vec<Region> = [Some(layout.lyrics), layout.credits, layout.art]
+        .into_iter()
+        .flatten()
+        .collect();
+    let mut animation = SvgAnimation::new(layout.columns, layout.lines, &panes);
+    let mut changes = None;
+    play(screen, lyrics, |time| {
+        // Only look at the screen when something was drawn
+        if changes != Some(virtual_screen.changes()) {
+            changes = Some(virtual_screen.changes());
+            animation.frame(time, &virtual_screen.cells());
+        }
+        Ok(())
+    })?;
+    animation.write(out, show_end(lyrics), background(theme))
+}
+
+// The whole show as `fps` pictures a second in `theme`'s colors, each handed
+// to `save` with its number, counting from 0. Returns how many there were.
+pub fn render_frames<F>(
+    lyrics: &[Lyric],
+    layout: &TerminalLayout,
+    theme: &Theme,
+    fps: u32,
+    format: ImageFormat,
+    mut save: F,
+) -> io::Result<u32>
+where
+    F: FnMut(u32, &[u8]) -> io::Result<()>,
+{
+    let (virtual_screen, screen) = virtual_show(layout, theme);
+    let mut frames = 0;
+    let mut image = Vec::new();
+    let mut changes = None;
+    play(screen, lyrics, |time| {
+        // Picture n is the screen at n / fps seconds
+        while frames as u64 * 100 / fps as u64 <= time as u64 {
+            // Most pictures are the same as the one before
+            if changes != Some(virtual_screen.changes()) {
+                changes = Some(virtual_screen.changes());
+                image.clear();
+                Picture::of_cells(&virtual_screen.cells(), background(theme))
+                    .write(&mut image, format)?;
+            }
+            save(frames, &image)?;
+            frames += 1;
+        }
+        Ok(())
+    })?;
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script::parse_script;
+
+    #[test]
+    fn renders_the_show_without_waiting() {
+        let lyrics = parse_script(
+            "100 0.1 0 \"Hi\"\n\
+             200 0 9\n",
+        )
+        .unwrap();
+        let layout = TerminalLayout::full(80, 24);
+        let render = || {
+            let mut cast = Vec::new();
+            let profile = TermProfile::from_name("xterm");
+            render_cast(&lyrics, &layout, profile, Palette::monochrome(), &mut cast).unwrap();
+            String::from_utf8(cast).unwrap()
+        };
+        let cast = render();
+        let lines: Vec<&str> = cast.lines().collect();
+
+        assert!(lines[0].starts_with("{\"version\": 2, \"width\": 80, \"height\": 24"));
+        // The frame first, then typing from the first step past the cue, a
+        // character every 0.05s, and the cursor on to the next line
+        assert!(lines[1].starts_with("[0.000000, \"o\", "));
+        assert!(lines[2].starts_with("[1.010000, \"o\", \"\\u001b[2;2HH"));
+        assert!(lines[3].starts_with("[1.060000, \"o\", \"\\u001b[2;3Hi"));
+        assert!(lines[4].starts_with("[1.110000, \"o\", \"\\u001b[3;2H"));
+        // The terminal back as it was at the end of the show
+        assert!(lines[5].starts_with("[4.000000, \"o\", "));
+        assert!(lines[5].ends_with("\\u001b[?1049l\"]"));
+        assert_eq!(lines.len(), 6);
+        // The same every time
+        assert_eq!(cast, render());
+    }
+
+    #[test]
+    fn renders_a_picture_per_frame() {
+        let lyrics = parse_script(
+            "100 0.1 0 \"Hi\"\n\
+             200 0 9\n",
+        )
+        .unwrap();
+        let layout = TerminalLayout::full(80, 24);
+        let render = || {
+            let mut frames = Vec::new();
+            let theme = crate::theme::built_in_themes().remove(0);
+            let count = render_frames(
+                &lyrics,
+                &layout,
+                &theme,
+                10,
+                ImageFormat::Ppm,
+                |n, image| {
+                    assert_eq!(n as usize, frames.len());
+                    frames.push(image.to_vec());
+                    Ok(())
+                },
+            )
+            .unwrap();
+            assert_eq!(count as usize, frames.len());
+            frames
+        };
+        let frames = render();
+
+        // A tenth of a second each, up to and including the end
+        assert_eq!(frames.len() as u32, show_end(&lyrics) / 10 + 1);
+        assert!(frames[0].starts_with(b"P6\n960 576\n255\n"));
+        // Nothing typed until after the first cue, then "Hi" over 0.1s
+        assert_eq!(frames[0], frames[10]);
+        assert_ne!(frames[10], frames[11]);
+        assert_eq!(frames[11], frames[12]);
+        assert_eq!(frames, render());
+    }
+}
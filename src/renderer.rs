@@ -0,0 +1,112 @@
+// renderer.rs
+// Everything the show needs from whatever it is drawn on. Positions are
+// 1-based like the rest of the drawing code.
+
+use crossterm::{
+    cursor,
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+    QueueableCommand,
+};
+use std::io::{self, Write};
+
+pub trait Renderer: Send {
+    fn move_to(&mut self, x: u16, y: u16) -> io::Result<()>;
+
+    // Print at the cursor, "\r\n" starts the next line
+    fn print(&mut self, text: &str) -> io::Result<()>;
+
+    fn clear_all(&mut self) -> io::Result<()>;
+
+    fn set_colors(&mut self, foreground: Color, background: Color) -> io::Result<()>;
+
+    fn reset_colors(&mut self) -> io::Result<()>;
+
+    // Switch to and back from a screen of our own, when there is one
+    fn enter_alt_screen(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn leave_alt_screen(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn show_cursor(&mut self, _shown: bool) -> io::Result<()> {
+        Ok(())
+    }
+
+    // Send everything drawn so far on its way
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+// ANSI escape sequences written with crossterm, to our own terminal or any
+// other stream (a serial line, a file). Everything queued goes out in a
+// single write on flush.
+pub struct CrosstermRenderer<W: Write + Send> {
+    out: W,
+    queued: Vec<u8>,
+}
+
+impl<W: Write + Send> CrosstermRenderer<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            queued: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write + Send> Renderer for CrosstermRenderer<W> {
+    fn move_to(&mut self, x: u16, y: u16) -> io::Result<()> {
+        self.queued.queue(cursor::MoveTo(x - 1, y - 1))?;
+        Ok(())
+    }
+
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        self.queued.queue(Print(text))?;
+        Ok(())
+    }
+
+    fn clear_all(&mut self) -> io::Result<()> {
+        self.queued.queue(Clear(ClearType::All))?;
+        Ok(())
+    }
+
+    fn set_colors(&mut self, foreground: Color, background: Color) -> io::Result<()> {
+        self.queued.queue(SetForegroundColor(foreground))?;
+        self.queued.queue(SetBackgroundColor(background))?;
+        Ok(())
+    }
+
+    fn reset_colors(&mut self) -> io::Result<()> {
+        self.queued.queue(ResetColor)?;
+        Ok(())
+    }
+
+    fn show_cursor(&mut self, shown: bool) -> io::Result<()> {
+        if shown {
+            self.queued.queue(cursor::Show)?;
+        } else {
+            self.queued.queue(cursor::Hide)?;
+        }
+        Ok(())
+    }
+
+    fn enter_alt_screen(&mut self) -> io::Result<()> {
+        self.queued.queue(EnterAlternateScreen)?;
+        Ok(())
+    }
+
+    fn leave_alt_screen(&mut self) -> io::Result<()> {
+        self.queued.queue(LeaveAlternateScreen)?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.queued.is_empty() {
+            self.out.write_all(&self.queued)?;
+            self.queued.clear();
+        }
+        self.out.flush()
+    }
+}
@@ -0,0 +1,274 @@
+// script.rs
+// Text format for show timelines, so a show can be edited without recompiling.
+//
+// One cue per line:
+//
+//     <time> <interval> <mode> ["words"]  [# comment]
+//
+// time      centiseconds since the show started
+// interval  seconds spent typing the words, negative to spread until next cue
+// mode      0 line + newline, 1 line without newline, 2 ASCII art (words is
+//           the art index), 3 clear lyrics, 4 start music, 5 start credits,
+//           9 end of show
+// words     double-quoted, supports \\, \", \0, \n, \t and \u{XXXX} escapes
+//
+// Blank lines and lines starting with `#` are ignored.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::lrc::parse_lrc;
+use crate::timeline::{Cue, Lyric};
+
+#[derive(Debug)]
+pub struct ScriptError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+// Load a show script, or import an LRC file when the extension says so
+pub fn load_script(path: &Path) -> Result<Vec<Lyric>, ScriptError> {
+    let source = fs::read_to_string(path).map_err(|e| ScriptError {
+        line: 0,
+        message: format!("cannot read {}: {}", path.display(), e),
+    })?;
+    let is_lrc = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("lrc"));
+    if is_lrc {
+        parse_lrc(&source)
+    } else {
+        parse_script(&source)
+    }
+}
+
+pub fn parse_script(source: &str) -> Result<Vec<Lyric>, ScriptError> {
+    let mut lyrics = Vec::new();
+
+    for (index, raw) in source.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: String| ScriptError {
+            line: index + 1,
+            message,
+        };
+
+        let (time, rest) = next_field(line);
+        let (interval, rest) = next_field(rest);
+        let (mode, rest) = next_field(rest);
+
+        let time = time
+            .parse::<u32>()
+            .map_err(|_| error(format!("invalid time `{}`", time)))?;
+        // NaN or infinite waits can't be slept for
+        let interval = interval
+            .parse::<f32>()
+            .ok()
+            .filter(|interval| interval.is_finite())
+            .ok_or_else(|| error(format!("invalid interval `{}`", interval)))?;
+        let mode = mode
+            .parse::<u8>()
+            .map_err(|_| error(format!("invalid mode `{}`", mode)))?;
+
+        let rest = rest.trim_start();
+        let (words, rest) = if rest.starts_with('"') {
+            parse_quoted(rest).map_err(error)?
+        } else {
+            (String::new(), rest)
+        };
+
+        let rest = rest.trim_start();
+        if !rest.is_empty() && !rest.starts_with('#') {
+            return Err(error(format!("unexpected `{}` after cue", rest)));
+        }
+
+        let cue = Cue::from_mode(mode, &words).map_err(error)?;
+        lyrics.push(Lyric::new(cue, time, interval));
+    }
+
+    Ok(lyrics)
+}
+
+// Split off the next whitespace separated field
+fn next_field(text: &str) -> (&str, &str) {
+    let text = text.trim_start();
+    match text.find(char::is_whitespace) {
+        Some(end) => (&text[..end], &text[end..]),
+        None => (text, ""),
+    }
+}
+
+// Parse a double-quoted string at the start of `text`, returning it unescaped
+// together with whatever follows the closing quote
+fn parse_quoted(text: &str) -> Result<(String, &str), String> {
+    let mut words = String::new();
+    let mut chars = text.char_indices().skip(1);
+
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '"' => return Ok((words, &text[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, '\\')) => words.push('\\'),
+                Some((_, '"')) => words.push('"'),
+                Some((_, '0')) => words.push('\0'),
+                Some((_, 'n')) => words.push('\n'),
+                Some((_, 't')) => words.push('\t'),
+                Some((start, 'u')) => {
+                    let tail = &text[start + 1..];
+                    let end = tail
+                        .find('}')
+                        .filter(|_| tail.starts_with('{'))
+                        .ok_or("expected `\\u{XXXX}` escape")?;
+                    let code = u32::from_str_radix(&tail[1..end], 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| format!("invalid escape `\\u{}`", &tail[..=end]))?;
+                    words.push(code);
+                    // Skip the `{XXXX}` part we just consumed
+                    for _ in 0..tail[..=end].chars().count() {
+                        chars.next();
+                    }
+                }
+                Some((_, other)) => return Err(format!("unknown escape `\\{}`", other)),
+                None => break,
+            },
+            _ => words.push(ch),
+        }
+    }
+
+    Err("unterminated string".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::get_lyrics;
+
+    fn error(source: &str) -> String {
+        parse_script(source).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn unescapes_quoted_words() {
+        let lyrics = parse_script(r#"100 0.5 1 "a\\b \"c\" \0\n\t\u{41}\u{1F600}""#).unwrap();
+        assert_eq!(
+            lyrics[0].cue,
+            Cue::Line {
+                text: "a\\b \"c\" \0\n\tA\u{1F600}".to_string(),
+                newline: false
+            }
+        );
+        assert_eq!(lyrics[0].time, 100);
+        assert_eq!(lyrics[0].interval, 0.5);
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let lyrics = parse_script(
+            "# A show\n\
+             \n\
+             \x20  # indented comment\n\
+             0 -1 0 \"Hi # not a comment\"  # but this is\n\
+             710 0 4 # no words\n\
+             800 0 2 \"3\"\n",
+        )
+        .unwrap();
+        assert_eq!(lyrics.len(), 3);
+        assert_eq!(
+            lyrics[0].cue,
+            Cue::Line {
+                text: "Hi # not a comment".to_string(),
+                newline: true
+            }
+        );
+        assert_eq!(lyrics[1].cue, Cue::StartMusic);
+        assert_eq!(lyrics[2].cue, Cue::ShowArt(3));
+    }
+
+    #[test]
+    fn reports_errors_with_their_line() {
+        let cases = [
+            ("x 0 0", "line 2: invalid time `x`"),
+            ("100 fast 0", "line 2: invalid interval `fast`"),
+            ("100 NaN 0 \"Hi\"", "line 2: invalid interval `NaN`"),
+            ("100 inf 0 \"Hi\"", "line 2: invalid interval `inf`"),
+            ("100 0 x", "line 2: invalid mode `x`"),
+            ("100 0 7", "line 2: unknown mode `7`"),
+            (
+                "100 0 2 \"a1\"",
+                "line 2: art cue needs an art index, found `a1`",
+            ),
+            ("100 0 0 \"Hi", "line 2: unterminated string"),
+            ("100 0 0 \"\\q\"", "line 2: unknown escape `\\q`"),
+            ("100 0 0 \"\\u41\"", "line 2: expected `\\u{XXXX}` escape"),
+            (
+                "100 0 0 \"\\u{D800}\"",
+                "line 2: invalid escape `\\u{D800}`",
+            ),
+            (
+                "100 0 0 \"Hi\" there",
+                "line 2: unexpected `there` after cue",
+            ),
+        ];
+        for (line, message) in cases {
+            assert_eq!(error(&format!("0 0 3\n{}\n", line)), message);
+        }
+    }
+
+    // The cue written back as a script line
+    fn script_line(lyric: &Lyric) -> String {
+        let (mode, words) = match &lyric.cue {
+            Cue::Line { text, newline } => (if *newline { 0 } else { 1 }, text.clone()),
+            Cue::ShowArt(art) => (2, art.to_string()),
+            Cue::ClearLyrics => (3, String::new()),
+            Cue::StartMusic => (4, String::new()),
+            Cue::StartCredits => (5, String::new()),
+            Cue::End => (9, String::new()),
+        };
+        let mut quoted = String::new();
+        for ch in words.chars() {
+            match ch {
+                '\\' => quoted.push_str("\\\\"),
+                '"' => quoted.push_str("\\\""),
+                '\0' => quoted.push_str("\\0"),
+                '\n' => quoted.push_str("\\n"),
+                '\t' => quoted.push_str("\\t"),
+                ch if ch.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", ch as u32)),
+                ch => quoted.push(ch),
+            }
+        }
+        format!(
+            "{} {:?} {} \"{}\"",
+            lyric.time, lyric.interval, mode, quoted
+        )
+    }
+
+    #[test]
+    fn built_in_script_round_trips() {
+        let lyrics = get_lyrics();
+        let written: Vec<String> = lyrics.iter().map(script_line).collect();
+        let reparsed = parse_script(&written.join("\n")).unwrap();
+
+        assert_eq!(reparsed.len(), lyrics.len());
+        for (before, after) in lyrics.iter().zip(&reparsed) {
+            assert_eq!(before.cue, after.cue);
+            assert_eq!(before.time, after.time);
+            assert_eq!(before.interval, after.interval);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+// serial.rs
+// Drive a terminal hanging off a serial line (or any device, file or pty)
+// instead of our own stdout
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Start, eight data bits and stop bit on the wire for every byte
+const BITS_PER_BYTE: u64 = 10;
+
+// Line rates the serial port can be set to
+pub const BAUD_RATES: &[u32] = &[300, 1200, 2400, 4800, 9600, 19200, 38400, 57600, 115200];
+
+// Writes no faster than the line can carry, so output doesn't pile up in
+// buffers and fall behind the show clock
+pub struct SerialOutput {
+    port: File,
+    baud: u32,
+    // When everything written so far will have gone over the line
+    sent_at: Instant,
+}
+
+impl SerialOutput {
+    pub fn open(path: &Path, baud: u32) -> io::Result<Self> {
+        let port = OpenOptions::new().read(true).write(true).open(path)?;
+        #[cfg(unix)]
+        configure(&port, baud)?;
+        Ok(Self {
+            port,
+            baud,
+            sent_at: Instant::now(),
+        })
+    }
+}
+
+impl Write for SerialOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.port.write(buf)?;
+        let line_time =
+            Duration::from_micros(written as u64 * BITS_PER_BYTE * 1_000_000 / self.baud as u64);
+        self.sent_at = std::cmp::max(self.sent_at, Instant::now()) + line_time;
+        Ok(written)
+    }
+
+    // Returns once the line caught up with everything written
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()?;
+        let now = Instant::now();
+        if self.sent_at > now {
+            thread::sleep(self.sent_at - now);
+        }
+        Ok(())
+    }
+}
+
+// Raw 8N1 at `baud`, anything that isn't a terminal (e.g. a plain file) is
+// left alone
+#[cfg(unix)]
+fn configure(port: &File, baud: u32) -> io::Result<()> {
+    use rustix::termios::{self, ControlModes, OptionalActions};
+
+    if !termios::isatty(port) {
+        return Ok(());
+    }
+    let mut attributes = termios::tcgetattr(port)?;
+    attributes.make_raw();
+    attributes.control_modes |= ControlModes::CLOCAL | ControlModes::CREAD;
+    attributes.set_speed(baud)?;
+    termios::tcsetattr(port, OptionalActions::Now, &attributes)?;
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use rustix::pty::{self, OpenptFlags};
+    use std::io::Read;
+    use std::path::PathBuf;
+
+    #[test]
+    fn paces_output_to_the_baud_rate() {
+        let master = pty::openpt(OpenptFlags::RDWR | OpenptFlags::NOCTTY).unwrap();
+        pty::grantpt(&master).unwrap();
+        pty::unlockpt(&master).unwrap();
+        let slave = PathBuf::from(
+            pty::ptsname(&master, Vec::new())
+                .unwrap()
+                .into_string()
+                .unwrap(),
+        );
+
+        // 120 bytes at 2400 baud are half a second on the line
+        let mut port = SerialOutput::open(&slave, 2400).unwrap();
+        let speed = rustix::termios::tcgetattr(&port.port)
+            .unwrap()
+            .output_speed();
+        assert_eq!(speed, 2400);
+        let start = Instant::now();
+        port.write_all(&[b'x'; 120]).unwrap();
+        port.flush().unwrap();
+        // Never faster than the line, however long a busy machine takes
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(500), "{:?}", elapsed);
+
+        let mut received = [0; 120];
+        File::from(master).read_exact(&mut received).unwrap();
+        assert_eq!(received, [b'x'; 120]);
+    }
+}
@@ -0,0 +1,199 @@
+// show.rs
+// The live show: cues run as the show clock reaches them, lyrics typed and
+// art drawn on the screen's render thread, credits scrolled by their own
+
+use std::time::Duration;
+
+use crate::audio::{AudioError, AudioOutput, Music, MusicData, SharedMusic};
+use crate::clock::SharedClock;
+use crate::timeline::{char_interval, state_at, Cue, Lyric, ShowState};
+use crate::tui_draw::{draw_ascii_art, draw_lyrics, start_credits, ScreenHandle, CREDITS_DURATION};
+
+// Shown in the frame when the music was wanted but can't play
+pub const AUDIO_UNAVAILABLE: &str = "audio unavailable";
+
+// Play `lyrics` on `screen` as `clock` goes, with the music of `audio` when
+// there is any, until the End cue. Returns why the music stopped playing, if
+// it failed to start.
+pub fn run_show(
+    screen: &ScreenHandle,
+    clock: &SharedClock,
+    lyrics: &[Lyric],
+    audio: Option<&(AudioOutput, MusicData)>,
+    music: &SharedMusic,
+) -> Option<AudioError> {
+    let mut current_lyric = 0;
+    let mut x = 0u16;
+    let mut y = 0u16;
+    let mut credits_started = false;
+    let mut seeks = clock.seeks();
+    // Why the music stopped playing
+    let mut music_failure = None;
+
+    //Print out lyric line-by-line
+    while current_lyric < lyrics.len() && lyrics[current_lyric].cue != Cue::End {
+        // Someone seeked, redraw everything as it would be at the new time
+        if clock.seeks() != seeks {
+            seeks = clock.seeks();
+            let now = clock.now();
+            let state = state_at(lyrics, now.as_millis() as u32 / 10);
+
+            let lines = state.lines.clone();
+            let art = state.art;
+            screen.draw(move |screen| {
+                screen.redraw_lyrics(&lines)?;
+                screen.redraw_ascii_art(art)
+            });
+            if let Some(credits_start) = state.credits {
+                if !credits_started {
+                    credits_started = true;
+                    start_credits(
+                        screen.clone(),
+                        clock.clone(),
+                        centis(credits_start),
+                        CREDITS_DURATION,
+                    );
+                }
+            }
+            if let Some(audio) = audio {
+                sync_music(screen, music, audio, &state, now, clock, &mut music_failure);
+            }
+
+            current_lyric = state.next_cue;
+            x = state.x;
+            y = state.y;
+            let (cursor_x, cursor_y) = (x, y);
+            screen.draw(move |screen| screen.move_cursor(cursor_x, cursor_y));
+
+            // Finish typing the line the seek landed in
+            if state.typed > 0 {
+                if let Cue::Line { text, newline } = &lyrics[current_lyric].cue {
+                    let rest: String = text.chars().skip(state.typed).collect();
+                    let interval = char_interval(lyrics, current_lyric);
+                    x = draw_lyrics(screen, clock, &rest, x, y, interval, *newline);
+                    if *newline {
+                        y += 1;
+                    }
+                }
+                current_lyric += 1;
+            }
+            continue;
+        }
+
+        let current_time = clock.now().as_millis() as u32 / 10;
+        //Each line of lyric have it own "timestamp", line will start printing again when "current_time" pass it
+        if current_time > lyrics[current_lyric].time {
+            let lyric = &lyrics[current_lyric];
+
+            match &lyric.cue {
+                Cue::Line { text, newline } => {
+                    let interval = char_interval(lyrics, current_lyric);
+                    x = draw_lyrics(screen, clock, text, x, y, interval, *newline);
+                    if *newline {
+                        y += 1;
+                    }
+                }
+                Cue::ShowArt(art_index) => {
+                    draw_ascii_art(screen, clock, *art_index);
+                }
+                Cue::ClearLyrics => {
+                    screen.draw(|screen| screen.clear_lyrics());
+                    x = 0;
+                    y = 0;
+                }
+                Cue::StartMusic => {
+                    if let Some((output, data)) = audio {
+                        let mut music = music.lock().unwrap();
+                        match music.as_mut() {
+                            // Reached again after seeking back before it
+                            Some(music) => {
+                                music.seek(centis(lyric.time), clock.seeks());
+                                music.set_paused(clock.is_paused());
+                            }
+                            None => {
+                                *music = start_music(
+                                    screen,
+                                    output,
+                                    data,
+                                    centis(lyric.time),
+                                    clock.seeks(),
+                                    &mut music_failure,
+                                )
+                            }
+                        }
+                    }
+                }
+                Cue::StartCredits => {
+                    if !credits_started {
+                        credits_started = true;
+                        start_credits(
+                            screen.clone(),
+                            clock.clone(),
+                            centis(lyric.time),
+                            CREDITS_DURATION,
+                        );
+                    }
+                }
+                Cue::End => {}
+            }
+
+            current_lyric += 1;
+        }
+
+        clock.sleep(Duration::from_millis(1));
+    }
+
+    music_failure
+}
+
+fn centis(time: u32) -> Duration {
+    Duration::from_millis(time as u64 * 10)
+}
+
+// Play the music from `start`. When that fails the show goes on muted, saying
+// so in the frame, and the error is kept for after the show.
+fn start_music(
+    screen: &ScreenHandle,
+    output: &AudioOutput,
+    data: &MusicData,
+    start: Duration,
+    seeks: u64,
+    failure: &mut Option<AudioError>,
+) -> Option<Music> {
+    match Music::start(output, data.clone(), start, seeks) {
+        Ok(music) => Some(music),
+        Err(e) => {
+            screen.draw(|screen| screen.draw_status(AUDIO_UNAVAILABLE));
+            *failure = Some(e);
+            None
+        }
+    }
+}
+
+// Bring the music to where it would be at show time `now`
+fn sync_music(
+    screen: &ScreenHandle,
+    music: &SharedMusic,
+    (output, data): &(AudioOutput, MusicData),
+    state: &ShowState,
+    now: Duration,
+    clock: &SharedClock,
+    failure: &mut Option<AudioError>,
+) {
+    let mut music = music.lock().unwrap();
+    match (state.music, music.as_mut()) {
+        (Some(_), Some(music)) => {
+            music.seek(now, clock.seeks());
+            music.set_paused(clock.is_paused());
+        }
+        (Some(start), None) => {
+            *music = start_music(screen, output, data, centis(start), clock.seeks(), failure);
+            if let Some(music) = music.as_mut() {
+                music.seek(now, clock.seeks());
+                music.set_paused(clock.is_paused());
+            }
+        }
+        (None, Some(music)) => music.hold(),
+        (None, None) => {}
+    }
+}
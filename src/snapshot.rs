@@ -0,0 +1,162 @@
+// snapshot.rs
+// The show as it looks at a single point in time, drawn without waiting
+
+use std::io;
+use std::time::Duration;
+
+use crate::layout::TerminalLayout;
+use crate::term::TermProfile;
+use crate::theme::Palette;
+use crate::timeline::{state_at, Lyric};
+use crate::tui_draw::{credits_shown_at, Screen, CREDITS_DURATION};
+use crate::virtual_screen::VirtualScreen;
+
+// Draw everything on screen at show time `time` (centiseconds)
+pub fn draw_show_at(screen: &mut Screen, lyrics: &[Lyric], time: u32) -> io::Result<()> {
+    let state = state_at(lyrics, time);
+    screen.clear_screen()?;
+    screen.draw_frame()?;
+    screen.redraw_lyrics(&state.lines)?;
+    screen.redraw_ascii_art(state.art)?;
+    if let Some(start) = state.credits {
+        let shown = credits_shown_at(centis(start), CREDITS_DURATION, centis(time));
+        screen.redraw_credits(shown)?;
+    }
+    screen.move_cursor(state.x, state.y)?;
+    screen.present()
+}
+
+// The screen laid out as `layout` at show time `time`, as text
+pub fn dump_frame(lyrics: &[Lyric], layout: &TerminalLayout, time: u32) -> io::Result<String> {
+    let virtual_screen = VirtualScreen::new(layout.columns, layout.lines);
+    let mut screen = Screen::new(
+        Box::new(virtual_screen.clone()),
+        TermProfile::from_name("xterm"),
+        Palette::monochrome(),
+        None,
+        layout,
+    );
+    draw_show_at(&mut screen, lyrics, time)?;
+    Ok(virtual_screen.contents())
+}
+
+fn centis(time: u32) -> Duration {
+    Duration::from_millis(time as u64 * 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::get_lyrics;
+    use crate::render::play;
+    use crate::timeline::Cue;
+    use std::fs;
+
+    // Run with UPDATE_SNAPSHOTS=1 to accept the current output
+    const SNAPSHOT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/snapshots/still_alive.txt"
+    );
+
+    // How long after its cue art is taken to be all there (centiseconds)
+    const ART_DRAWN: u32 = 50;
+
+    // The end of every page, every art swap and the end of the credits
+    fn key_times(lyrics: &[Lyric]) -> Vec<(u32, String)> {
+        let mut times = Vec::new();
+        let mut page = 1;
+        for lyric in lyrics {
+            match &lyric.cue {
+                Cue::ClearLyrics => {
+                    times.push((lyric.time, format!("end of page {}", page)));
+                    page += 1;
+                }
+                Cue::End => times.push((lyric.time, "end of show".to_string())),
+                // Art is drawn a line a centisecond, once any line being
+                // typed is done
+                Cue::ShowArt(art) => times.push((lyric.time + ART_DRAWN, format!("art {}", art))),
+                Cue::StartCredits => {
+                    let end = lyric.time + CREDITS_DURATION.as_millis() as u32 / 10;
+                    times.push((end, "end of credits".to_string()));
+                }
+                _ => {}
+            }
+            if lyric.cue == Cue::End {
+                break;
+            }
+        }
+        times.sort_by_key(|(time, _)| *time);
+        times.dedup_by_key(|(time, _)| *time);
+        times
+    }
+
+    // The screen at each of `times` as the show plays live, on the clock
+    // stepped a centisecond at a time
+    fn play_live(lyrics: &[Lyric], layout: &TerminalLayout, times: &[u32]) -> Vec<String> {
+        let virtual_screen = VirtualScreen::new(layout.columns, layout.lines);
+        let screen = Screen::new(
+            Box::new(virtual_screen.clone()),
+            TermProfile::from_name("xterm"),
+            Palette::monochrome(),
+            None,
+            layout,
+        );
+        let mut screens = Vec::new();
+        play(screen, lyrics, |time| {
+            if times.contains(&time) {
+                screens.push(virtual_screen.contents());
+            }
+            Ok(())
+        })
+        .unwrap();
+        screens
+    }
+
+    #[test]
+    fn built_in_show_matches_snapshots() {
+        let lyrics = get_lyrics();
+        let layout = TerminalLayout::full(80, 24);
+        let key_times = key_times(&lyrics);
+        let times: Vec<u32> = key_times.iter().map(|(time, _)| *time).collect();
+        let screens = play_live(&lyrics, &layout, &times);
+        let mut output = String::new();
+        for ((time, label), screen) in key_times.iter().zip(&screens) {
+            output.push_str(&format!(
+                "=== {}.{:02}s {} ===\n",
+                time / 100,
+                time % 100,
+                label
+            ));
+            output.push_str(screen);
+            // Seeking there draws the same
+            assert_eq!(
+                screen,
+                &dump_frame(&lyrics, &layout, *time).unwrap(),
+                "{}",
+                label
+            );
+        }
+
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            fs::write(SNAPSHOT, &output).unwrap();
+            return;
+        }
+        let expected = fs::read_to_string(SNAPSHOT).unwrap_or_default();
+        if output != expected {
+            let (line, (got, want)) = output
+                .lines()
+                .zip(expected.lines().chain(std::iter::repeat("<missing>")))
+                .enumerate()
+                .find(|(_, (got, want))| got != want)
+                .unwrap_or((expected.lines().count(), ("<missing>", "")));
+            panic!(
+                "screen differs from {} at line {}:\n  got:  {:?}\n  want: {:?}\n\
+                 rerun with UPDATE_SNAPSHOTS=1 if the change is intended",
+                SNAPSHOT,
+                line + 1,
+                got,
+                want
+            );
+        }
+    }
+}
@@ -0,0 +1,264 @@
+// svg.rs
+// The show as one self-contained animated SVG. Every screen row is cut at
+// the pane edges into stretches, and each look a stretch had becomes a line
+// of text that CSS keyframes show from when it was drawn until it changed.
+
+use crossterm::style::Color;
+use std::io::{self, Write};
+
+use crate::compositor::Region;
+use crate::theme::Rgb;
+
+const FONT_SIZE: f64 = 16.0;
+const CELL_WIDTH: f64 = 9.6;
+const LINE_HEIGHT: f64 = 20.0;
+// Where the text sits in its line
+const BASELINE: f64 = 15.0;
+// Text drawn without a theme color
+const DEFAULT_FOREGROUND: Rgb = (192, 192, 192);
+
+type Cells = Vec<(char, Option<Color>)>;
+
+// A row stretch as it looked from `start` to `end` (centiseconds)
+struct Shown {
+    x: usize,
+    y: usize,
+    cells: Cells,
+    start: u32,
+    end: u32,
+}
+
+pub struct SvgAnimation {
+    columns: u16,
+    lines: u16,
+    // Stretches of every row, from the first column to past the last
+    stretches: Vec<Vec<(usize, usize)>>,
+    // What every stretch shows now, and since when
+    current: Vec<Vec<(Cells, u32)>>,
+    shown: Vec<Shown>,
+}
+
+impl SvgAnimation {
+    // A `columns` x `lines` screen with rows cut at the edges of `panes`
+    pub fn new(columns: u16, lines: u16, panes: &[Region]) -> Self {
+        let width = columns as usize;
+        let stretches: Vec<Vec<(usize, usize)>> = (1..=lines)
+            .map(|y| {
+                let mut cuts = vec![0, width];
+                for pane in panes
+                    .iter()
+                    .filter(|pane| (pane.y..pane.y + pane.height).contains(&y))
+                {
+                    let left = pane.x as usize - 1;
+                    cuts.push(left.min(width));
+                    cuts.push((left + pane.width as usize).min(width));
+                }
+                cuts.sort_unstable();
+                cuts.dedup();
+                cuts.windows(2).map(|cut| (cut[0], cut[1])).collect()
+            })
+            .collect();
+        let current = stretches
+            .iter()
+            .map(|row| vec![(Cells::new(), 0); row.len()])
+            .collect();
+        Self {
+            columns,
+            lines,
+            stretches,
+            current,
+            shown: Vec::new(),
+        }
+    }
+
+    // The screen looks like `cells` from show time `time` (centiseconds) on
+    pub fn frame(&mut self, time: u32, cells: &[Cells]) {
+        for (y, row) in cells.iter().enumerate().take(self.stretches.len()) {
+            for (i, &(left, right)) in self.stretches[y].iter().enumerate() {
+                let stretch = &row[left..right];
+                let (current, since) = &mut self.current[y][i];
+                if current.as_slice() == stretch {
+                    continue;
+                }
+                let before = std::mem::replace(current, stretch.to_vec());
+                if !is_blank(&before) {
+                    self.shown.push(Shown {
+                        x: left,
+                        y,
+                        cells: before,
+                        start: *since,
+                        end: time,
+                    });
+                }
+                *since = time;
+            }
+        }
+    }
+
+    // Write the animation, repeating every `end` centiseconds, on
+    // `background`
+    pub fn write<W: Write>(mut self, mut out: W, end: u32, background: Rgb) -> io::Result<()> {
+        for (y, row) in self.current.iter_mut().enumerate() {
+            for (i, (cells, since)) in row.iter_mut().enumerate() {
+                if !is_blank(cells) {
+                    self.shown.push(Shown {
+                        x: self.stretches[y][i].0,
+                        y,
+                        cells: std::mem::take(cells),
+                        start: *since,
+                        end,
+                    });
+                }
+            }
+        }
+
+        let width = self.columns as f64 * CELL_WIDTH;
+        let height = self.lines as f64 * LINE_HEIGHT;
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+             viewBox=\"0 0 {0} {1}\" xml:space=\"preserve\">",
+            width, height
+        )?;
+        writeln!(out, "<style>")?;
+        writeln!(
+            out,
+            "text {{ font-family: 'DejaVu Sans Mono', Menlo, Consolas, monospace; \
+             font-size: {}px; white-space: pre; visibility: hidden; \
+             animation-duration: {:.2}s; animation-timing-function: step-end; \
+             animation-iteration-count: infinite; }}",
+            FONT_SIZE,
+            end as f64 / 100.0
+        )?;
+        let percent = |time: u32| time as f64 * 100.0 / end.max(1) as f64;
+        for (i, shown) in self.shown.iter().enumerate() {
+            let mut keyframes = String::new();
+            if shown.start > 0 {
+                keyframes.push_str("0% { visibility: hidden } ");
+            }
+            keyframes.push_str(&format!(
+                "{:.4}% {{ visibility: visible }}",
+                percent(shown.start)
+            ));
+            if shown.end < end {
+                keyframes.push_str(&format!(
+                    " {:.4}% {{ visibility: hidden }}",
+                    percent(shown.end)
+                ));
+            }
+            writeln!(out, "@keyframes k{} {{ {} }}", i, keyframes)?;
+        }
+        writeln!(out, "</style>")?;
+        writeln!(
+            out,
+            "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+            css_color(background)
+        )?;
+
+        for (i, shown) in self.shown.iter().enumerate() {
+            let first = shown
+                .cells
+                .iter()
+                .position(|(ch, _)| *ch != ' ')
+                .unwrap_or(0);
+            let last = shown
+                .cells
+                .iter()
+                .rposition(|(ch, _)| *ch != ' ')
+                .unwrap_or(0)
+                + 1;
+            let cells = &shown.cells[first..last];
+            write!(
+                out,
+                "<text x=\"{:.1}\" y=\"{:.1}\" textLength=\"{:.1}\" style=\"animation-name: k{}\">",
+                (shown.x + first) as f64 * CELL_WIDTH,
+                shown.y as f64 * LINE_HEIGHT + BASELINE,
+                cells.len() as f64 * CELL_WIDTH,
+                i
+            )?;
+            // One span per color, blanks go along with the text before them
+            let mut spans: Vec<(Option<Color>, String)> = Vec::new();
+            for &(ch, color) in cells {
+                match spans.last_mut() {
+                    Some((span_color, text)) if ch == ' ' || *span_color == color => text.push(ch),
+                    _ => spans.push((color, ch.to_string())),
+                }
+            }
+            for (color, text) in spans {
+                write!(
+                    out,
+                    "<tspan fill=\"{}\">{}</tspan>",
+                    css_color(rgb(color)),
+                    xml_escape(&text)
+                )?;
+            }
+            writeln!(out, "</text>")?;
+        }
+        writeln!(out, "</svg>")?;
+        out.flush()
+    }
+}
+
+fn is_blank(cells: &[(char, Option<Color>)]) -> bool {
+    cells.iter().all(|(ch, _)| *ch == ' ')
+}
+
+// Colors come from a 24-bit palette, anything else is drawn in the default
+fn rgb(color: Option<Color>) -> Rgb {
+    match color {
+        Some(Color::Rgb { r, g, b }) => (r, g, b),
+        _ => DEFAULT_FOREGROUND,
+    }
+}
+
+fn css_color((r, g, b): Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_look_of_a_stretch_gets_its_keyframes() {
+        let amber = Some(Color::Rgb {
+            r: 255,
+            g: 176,
+            b: 0,
+        });
+        let row = |text: &str| -> Cells { text.chars().map(|ch| (ch, amber)).collect() };
+        // The pane cuts the row into "|", "ab" and "|"
+        let mut animation = SvgAnimation::new(4, 1, &[Region::new(2, 1, 2, 1)]);
+        animation.frame(0, &[row("|  |")]);
+        animation.frame(100, &[row("|a |")]);
+        animation.frame(150, &[row("|<b|")]);
+
+        let mut svg = Vec::new();
+        animation.write(&mut svg, 200, (0, 0, 0)).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+
+        assert!(svg.contains("@keyframes k0 { 0% { visibility: hidden } 50.0000% { visibility: visible } 75.0000% { visibility: hidden } }"));
+        assert!(svg.contains("style=\"animation-name: k0\"><tspan fill=\"#ffb000\">a</tspan>"));
+        // The border is there from the start
+        assert!(svg.contains("@keyframes k1 { 0.0000% { visibility: visible } }"));
+        // Still there at the end, escaped
+        assert!(svg.contains(
+            "@keyframes k2 { 0% { visibility: hidden } 75.0000% { visibility: visible } }"
+        ));
+        assert!(svg.contains("<tspan fill=\"#ffb000\">&lt;b</tspan>"));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
@@ -0,0 +1,307 @@
+// term.rs
+// What the terminal on the other end can do, following the $TERM rules of
+// the Python original so real VT100/VT220 hardware gets a show it can display.
+// Where the system has a terminfo entry for $TERM, that has the final say.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TermProfile {
+    pub name: String,
+    // Alternate screen buffer, so the old screen comes back afterwards
+    pub alt_screen: bool,
+    pub color: bool,
+    // Without cursor addressing the lyrics are typed out like on a teletype
+    pub cursor_addressing: bool,
+    // Hardware terminals can't report their size, they are taken as 80x24
+    pub query_size: bool,
+}
+
+impl TermProfile {
+    // Profile for the terminal named by $TERM, checked against its terminfo
+    pub fn detect() -> Self {
+        match env::var("TERM") {
+            Ok(term) if !term.is_empty() => {
+                let mut profile = Self::from_name(&term);
+                if let Some(terminfo) = find_terminfo(&term) {
+                    profile.apply(&terminfo);
+                }
+                profile
+            }
+            // Windows consoles don't set $TERM
+            _ if cfg!(windows) => Self::from_name("xterm"),
+            // Most likely a getty on a serial line
+            _ => Self::from_name("vt220"),
+        }
+    }
+
+    // Profile for a terminal type name as found in $TERM, e.g. `vt100`
+    pub fn from_name(name: &str) -> Self {
+        let name = name.to_lowercase();
+        let mut profile = TermProfile {
+            name: name.clone(),
+            alt_screen: true,
+            color: true,
+            cursor_addressing: true,
+            query_size: true,
+        };
+
+        if name == "dumb" {
+            profile.alt_screen = false;
+            profile.color = false;
+            profile.cursor_addressing = false;
+            profile.query_size = false;
+        } else if let Some(model) = vt_model(&name) {
+            // Color came with the VT241
+            profile.alt_screen = false;
+            profile.color = model >= 241;
+            profile.query_size = false;
+        } else if name == "linux" {
+            profile.alt_screen = false;
+        }
+        profile
+    }
+
+    // What the terminfo entry says the terminal can do. Whether it can
+    // report its size is nothing terminfo knows about, that stays.
+    fn apply(&mut self, terminfo: &Terminfo) {
+        self.alt_screen = terminfo.alt_screen;
+        self.color = terminfo.colors >= 8;
+        self.cursor_addressing = terminfo.cursor_addressing;
+    }
+}
+
+// Model number of a `vt<digits>` terminal name, e.g. 220 for `vt220-w`
+fn vt_model(name: &str) -> Option<u32> {
+    name.match_indices("vt").find_map(|(i, _)| {
+        let digits: String = name[i + 2..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        digits.parse().ok()
+    })
+}
+
+// The capabilities of a compiled terminfo entry that matter for the show
+#[derive(Debug, PartialEq)]
+struct Terminfo {
+    // smcup
+    alt_screen: bool,
+    colors: i32,
+    // cup
+    cursor_addressing: bool,
+}
+
+// Where ncurses looks for compiled entries, in order
+fn terminfo_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = env::var_os("TERMINFO") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(home) = env::var_os("HOME") {
+        dirs.push(PathBuf::from(home).join(".terminfo"));
+    }
+    if let Ok(list) = env::var("TERMINFO_DIRS") {
+        dirs.extend(
+            list.split(':')
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from),
+        );
+    }
+    for dir in ["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"] {
+        dirs.push(PathBuf::from(dir));
+    }
+    dirs
+}
+
+// The entry for `name`, filed under its first letter, or its first byte in
+// hex on macOS
+fn find_terminfo(name: &str) -> Option<Terminfo> {
+    let first = name.bytes().next()?;
+    if name.contains('/') {
+        return None;
+    }
+    terminfo_dirs().into_iter().find_map(|dir| {
+        [(first as char).to_string(), format!("{:02x}", first)]
+            .iter()
+            .find_map(|sub| fs::read(dir.join(sub).join(name)).ok())
+            .and_then(|data| parse_terminfo(&data))
+    })
+}
+
+// Indices into the number and string sections, as in term.h
+const MAX_COLORS: usize = 13;
+const CURSOR_ADDRESS: usize = 10;
+const ENTER_CA_MODE: usize = 28;
+
+// A compiled entry: a header of six little-endian shorts, then the names,
+// booleans, numbers (shorts, or ints in the newer format) and string offsets.
+// Missing capabilities are negative or past the end of their section.
+fn parse_terminfo(data: &[u8]) -> Option<Terminfo> {
+    let short = |at: usize| -> Option<i32> {
+        let bytes = data.get(at..at + 2)?;
+        Some(i16::from_le_bytes([bytes[0], bytes[1]]) as i32)
+    };
+    let number_size = match short(0)? {
+        0o432 => 2,
+        0o1036 => 4,
+        _ => return None,
+    };
+    let count = |at: usize| short(at).and_then(|n| usize::try_from(n).ok());
+    let (names, booleans, numbers, strings) = (count(2)?, count(4)?, count(6)?, count(8)?);
+
+    // Numbers start on an even byte
+    let numbers_at = (12 + names + booleans + 1) & !1;
+    let strings_at = numbers_at + numbers * number_size;
+    if data.len() < strings_at + strings * 2 {
+        return None;
+    }
+    let colors = if MAX_COLORS < numbers {
+        let at = numbers_at + MAX_COLORS * number_size;
+        if number_size == 4 {
+            i32::from_le_bytes(data[at..at + 4].try_into().ok()?)
+        } else {
+            short(at)?
+        }
+    } else {
+        -1
+    };
+    let has_string = |index: usize| index < strings && short(strings_at + index * 2) >= Some(0);
+
+    Some(Terminfo {
+        alt_screen: has_string(ENTER_CA_MODE),
+        colors,
+        cursor_addressing: has_string(CURSOR_ADDRESS),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, alt_screen: bool, color: bool, cursor: bool, size: bool) -> TermProfile {
+        TermProfile {
+            name: name.to_string(),
+            alt_screen,
+            color,
+            cursor_addressing: cursor,
+            query_size: size,
+        }
+    }
+
+    #[test]
+    fn profiles_from_names() {
+        let cases = [
+            (
+                "xterm-256color",
+                profile("xterm-256color", true, true, true, true),
+            ),
+            ("LINUX", profile("linux", false, true, true, true)),
+            ("vt100", profile("vt100", false, false, true, false)),
+            ("vt220-w", profile("vt220-w", false, false, true, false)),
+            ("vt241", profile("vt241", false, true, true, false)),
+            ("dumb", profile("dumb", false, false, false, false)),
+            // Anything unknown is taken as a modern terminal
+            ("foo", profile("foo", true, true, true, true)),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(TermProfile::from_name(name), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn vt_models() {
+        assert_eq!(vt_model("vt100"), Some(100));
+        assert_eq!(vt_model("vt220-w"), Some(220));
+        assert_eq!(vt_model("xterm-vt320"), Some(320));
+        assert_eq!(vt_model("vt"), None);
+        assert_eq!(vt_model("xterm"), None);
+        assert_eq!(vt_model("dumb"), None);
+    }
+
+    // A compiled entry with the given number and string sections
+    fn entry(wide: bool, numbers: &[i32], strings: &[i16]) -> Vec<u8> {
+        let magic: i16 = if wide { 0o1036 } else { 0o432 };
+        let names = b"test|a test terminal\0";
+        let mut data = Vec::new();
+        for short in [
+            magic,
+            names.len() as i16,
+            1,
+            numbers.len() as i16,
+            strings.len() as i16,
+            0,
+        ] {
+            data.extend_from_slice(&short.to_le_bytes());
+        }
+        data.extend_from_slice(names);
+        data.push(1);
+        if data.len() % 2 == 1 {
+            data.push(0);
+        }
+        for &number in numbers {
+            if wide {
+                data.extend_from_slice(&number.to_le_bytes());
+            } else {
+                data.extend_from_slice(&(number as i16).to_le_bytes());
+            }
+        }
+        for &offset in strings {
+            data.extend_from_slice(&offset.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn reads_compiled_terminfo() {
+        let mut numbers = [-1; 14];
+        numbers[MAX_COLORS] = 256;
+        let mut strings = [-1; 29];
+        strings[CURSOR_ADDRESS] = 0;
+        strings[ENTER_CA_MODE] = 0;
+        let xterm = Terminfo {
+            alt_screen: true,
+            colors: 256,
+            cursor_addressing: true,
+        };
+        assert_eq!(
+            parse_terminfo(&entry(true, &numbers, &strings)),
+            Some(xterm)
+        );
+
+        // Too few numbers for colors, no smcup
+        let vt100 = Terminfo {
+            alt_screen: false,
+            colors: -1,
+            cursor_addressing: true,
+        };
+        assert_eq!(
+            parse_terminfo(&entry(false, &[80], &strings[..12])),
+            Some(vt100)
+        );
+
+        let nothing = Terminfo {
+            alt_screen: false,
+            colors: -1,
+            cursor_addressing: false,
+        };
+        assert_eq!(parse_terminfo(&entry(false, &[80], &[])), Some(nothing));
+
+        assert_eq!(parse_terminfo(b"not terminfo"), None);
+        let truncated = entry(false, &numbers, &strings);
+        assert_eq!(parse_terminfo(&truncated[..truncated.len() - 1]), None);
+    }
+
+    #[test]
+    fn terminfo_overrides_the_name_rules() {
+        let mut mono = TermProfile::from_name("xterm-mono");
+        mono.apply(&Terminfo {
+            alt_screen: true,
+            colors: -1,
+            cursor_addressing: true,
+        });
+        assert_eq!(mono, profile("xterm-mono", true, false, true, true));
+    }
+}
@@ -0,0 +1,487 @@
+// theme.rs
+// Color schemes for the show: a color for the frame, the lyrics, the credits
+// and the art on one background, brought down to what the terminal can show.
+//
+// Besides the built-in themes, more can be defined in a theme file:
+//
+//     theme = glados          # theme to use when --theme isn't given
+//
+//     [glados]
+//     background = #000000
+//     frame      = #806000
+//     lyrics     = #ffb000
+//     credits    = #ffb000    # frame, credits and art default to the lyrics
+//     art        = #ffd040
+//
+// Blank lines and `#` comments are ignored.
+
+use crossterm::style::Color;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::term::TermProfile;
+
+pub const DEFAULT_THEME: &str = "aperture-amber";
+
+// What a cell on screen belongs to, each has its own color
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Element {
+    Frame,
+    Lyrics,
+    Credits,
+    Art,
+}
+
+pub type Rgb = (u8, u8, u8);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThemeColors {
+    pub background: Rgb,
+    pub frame: Rgb,
+    pub lyrics: Rgb,
+    pub credits: Rgb,
+    pub art: Rgb,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    // `None` draws in the terminal's own colors
+    pub colors: Option<ThemeColors>,
+}
+
+pub fn built_in_themes() -> Vec<Theme> {
+    let theme = |name: &str, colors| Theme {
+        name: name.to_string(),
+        colors,
+    };
+    vec![
+        theme(
+            DEFAULT_THEME,
+            Some(ThemeColors {
+                background: (0, 0, 0),
+                frame: (255, 159, 0),
+                lyrics: (255, 176, 0),
+                credits: (255, 176, 0),
+                art: (255, 204, 0),
+            }),
+        ),
+        theme(
+            "green-phosphor",
+            Some(ThemeColors {
+                background: (0, 0, 0),
+                frame: (32, 192, 32),
+                lyrics: (51, 255, 51),
+                credits: (51, 255, 51),
+                art: (102, 255, 102),
+            }),
+        ),
+        theme(
+            "white",
+            Some(ThemeColors {
+                background: (0, 0, 0),
+                frame: (160, 160, 160),
+                lyrics: (240, 240, 240),
+                credits: (208, 208, 208),
+                art: (255, 255, 255),
+            }),
+        ),
+        theme("monochrome", None),
+    ]
+}
+
+// How many colors the terminal can show
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorDepth {
+    Monochrome,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorDepth {
+    // Colors of our own terminal, which `$COLORTERM` and `$NO_COLOR` tell
+    // more about than its profile
+    pub fn detect(profile: &TermProfile) -> Self {
+        if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return ColorDepth::Monochrome;
+        }
+        let depth = Self::for_profile(profile);
+        let colorterm = env::var("COLORTERM").unwrap_or_default().to_lowercase();
+        if depth != ColorDepth::Monochrome
+            && (colorterm == "truecolor"
+                || colorterm == "24bit"
+                || env::var_os("WT_SESSION").is_some())
+        {
+            ColorDepth::TrueColor
+        } else {
+            depth
+        }
+    }
+
+    // Colors a terminal of this type has, going by its name alone
+    pub fn for_profile(profile: &TermProfile) -> Self {
+        if !profile.color {
+            ColorDepth::Monochrome
+        } else if profile.name.contains("truecolor") || profile.name.contains("direct") {
+            ColorDepth::TrueColor
+        } else if profile.name.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+}
+
+// A theme's colors as sent to one terminal
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    // `None` when drawing without color
+    colors: Option<ThemeColors>,
+    depth: ColorDepth,
+}
+
+impl Palette {
+    pub fn new(theme: &Theme, depth: ColorDepth) -> Self {
+        let colors = theme.colors.filter(|_| depth != ColorDepth::Monochrome);
+        Self { colors, depth }
+    }
+
+    // Draw in the terminal's own colors
+    pub fn monochrome() -> Self {
+        Self {
+            colors: None,
+            depth: ColorDepth::Monochrome,
+        }
+    }
+
+    pub fn is_colored(&self) -> bool {
+        self.colors.is_some()
+    }
+
+    // Enough colors to tell shades of the theme's colors apart
+    pub fn has_shades(&self) -> bool {
+        self.is_colored() && matches!(self.depth, ColorDepth::Ansi256 | ColorDepth::TrueColor)
+    }
+
+    // Foreground and background for `element`, `shade` steps brighter
+    // (towards white) or darker (towards the background)
+    pub fn colors(&self, element: Element, shade: i8) -> Option<(Color, Color)> {
+        let colors = self.colors?;
+        let foreground = match element {
+            Element::Frame => colors.frame,
+            Element::Lyrics => colors.lyrics,
+            Element::Credits => colors.credits,
+            Element::Art => colors.art,
+        };
+        let foreground = match shade {
+            0 => foreground,
+            1.. => mix(foreground, (255, 255, 255), shade as u32 * 20),
+            _ => mix(
+                foreground,
+                colors.background,
+                shade.unsigned_abs() as u32 * 30,
+            ),
+        };
+        Some((self.convert(foreground), self.convert(colors.background)))
+    }
+
+    fn convert(&self, rgb: Rgb) -> Color {
+        match self.depth {
+            ColorDepth::TrueColor => Color::Rgb {
+                r: rgb.0,
+                g: rgb.1,
+                b: rgb.2,
+            },
+            ColorDepth::Ansi256 => Color::AnsiValue(ansi256(rgb)),
+            _ => ansi16(rgb),
+        }
+    }
+}
+
+// `percent` of the way from `a` to `b`
+fn mix(a: Rgb, b: Rgb, percent: u32) -> Rgb {
+    let percent = percent.min(100);
+    let channel = |x: u8, y: u8| ((x as u32 * (100 - percent) + y as u32 * percent) / 100) as u8;
+    (channel(a.0, b.0), channel(a.1, b.1), channel(a.2, b.2))
+}
+
+// Nearest color of the xterm 256 color palette, from its 6x6x6 cube or its
+// gray ramp
+fn ansi256(rgb: Rgb) -> u8 {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let level = |c: u8| match c {
+        0..=47 => 0,
+        48..=114 => 1,
+        _ => (c as usize - 35) / 40,
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (LEVELS[r], LEVELS[g], LEVELS[b]);
+
+    let average = (rgb.0 as usize + rgb.1 as usize + rgb.2 as usize) / 3;
+    let step = std::cmp::min(23, average.saturating_sub(3) / 10);
+    let gray = 8 + step as u8 * 10;
+
+    if distance(rgb, (gray, gray, gray)) < distance(rgb, cube) {
+        232 + step as u8
+    } else {
+        16 + (36 * r + 6 * g + b) as u8
+    }
+}
+
+// Nearest of the 16 standard colors
+fn ansi16(rgb: Rgb) -> Color {
+    const COLORS: [(Color, Rgb); 16] = [
+        (Color::Black, (0, 0, 0)),
+        (Color::DarkRed, (128, 0, 0)),
+        (Color::DarkGreen, (0, 128, 0)),
+        (Color::DarkYellow, (128, 128, 0)),
+        (Color::DarkBlue, (0, 0, 128)),
+        (Color::DarkMagenta, (128, 0, 128)),
+        (Color::DarkCyan, (0, 128, 128)),
+        (Color::Grey, (192, 192, 192)),
+        (Color::DarkGrey, (128, 128, 128)),
+        (Color::Red, (255, 0, 0)),
+        (Color::Green, (0, 255, 0)),
+        (Color::Yellow, (255, 255, 0)),
+        (Color::Blue, (0, 0, 255)),
+        (Color::Magenta, (255, 0, 255)),
+        (Color::Cyan, (0, 255, 255)),
+        (Color::White, (255, 255, 255)),
+    ];
+    COLORS
+        .iter()
+        .min_by_key(|(_, value)| distance(rgb, *value))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::White)
+}
+
+fn distance(a: Rgb, b: Rgb) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+#[derive(Debug)]
+pub struct ThemeError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+// Themes defined in a theme file, and the one it picks when there is one
+#[derive(Debug, Default, PartialEq)]
+pub struct ThemeFile {
+    pub theme: Option<String>,
+    pub themes: Vec<Theme>,
+}
+
+// Where the theme file is looked for when --theme-file isn't given
+pub fn default_theme_file() -> Option<PathBuf> {
+    let config = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None if cfg!(windows) => PathBuf::from(env::var_os("APPDATA")?),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config.join("portal-still-alive").join("themes.conf"))
+}
+
+pub fn load_theme_file(path: &Path) -> Result<ThemeFile, ThemeError> {
+    let source = fs::read_to_string(path).map_err(|e| ThemeError {
+        line: 0,
+        message: format!("cannot read {}: {}", path.display(), e),
+    })?;
+    parse_theme_file(&source)
+}
+
+pub fn parse_theme_file(source: &str) -> Result<ThemeFile, ThemeError> {
+    // Colors of the theme being read, by key, until its section ends
+    struct Section {
+        name: String,
+        line: usize,
+        colors: [Option<Rgb>; 5],
+    }
+    const KEYS: [&str; 5] = ["background", "frame", "lyrics", "credits", "art"];
+
+    fn finish(section: Section) -> Result<Theme, ThemeError> {
+        let [background, frame, lyrics, credits, art] = section.colors;
+        let lyrics = lyrics.ok_or_else(|| ThemeError {
+            line: section.line,
+            message: format!("theme `{}` has no lyrics color", section.name),
+        })?;
+        Ok(Theme {
+            name: section.name,
+            colors: Some(ThemeColors {
+                background: background.unwrap_or((0, 0, 0)),
+                frame: frame.unwrap_or(lyrics),
+                lyrics,
+                credits: credits.unwrap_or(lyrics),
+                art: art.unwrap_or(lyrics),
+            }),
+        })
+    }
+
+    let mut file = ThemeFile::default();
+    let mut section: Option<Section> = None;
+
+    for (index, raw) in source.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: String| ThemeError {
+            line: index + 1,
+            message,
+        };
+        // Names and values are single words, anything after them must be a
+        // comment
+        let comment = |rest: &str| {
+            let rest = rest.trim();
+            rest.is_empty() || rest.starts_with('#')
+        };
+
+        if let Some(rest) = line.strip_prefix('[') {
+            let name = rest
+                .split_once(']')
+                .filter(|(name, rest)| !name.trim().is_empty() && comment(rest))
+                .map(|(name, _)| name.trim())
+                .ok_or_else(|| error(format!("invalid section `{}`", line)))?;
+            if let Some(section) = section.take() {
+                file.themes.push(finish(section)?);
+            }
+            section = Some(Section {
+                name: name.to_string(),
+                line: index + 1,
+                colors: [None; 5],
+            });
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .and_then(|(key, rest)| {
+                let rest = rest.trim_start();
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                let (value, rest) = rest.split_at(end);
+                (!value.is_empty() && comment(rest)).then_some((key.trim(), value))
+            })
+            .ok_or_else(|| error(format!("expected `key = value`, found `{}`", line)))?;
+        match section.as_mut() {
+            None if key == "theme" => file.theme = Some(value.to_string()),
+            None => return Err(error(format!("unknown setting `{}`", key))),
+            Some(section) => {
+                let slot = KEYS
+                    .iter()
+                    .position(|k| *k == key)
+                    .ok_or_else(|| error(format!("unknown color `{}`", key)))?;
+                let color = parse_color(value)
+                    .ok_or_else(|| error(format!("invalid color `{}`, use #rrggbb", value)))?;
+                section.colors[slot] = Some(color);
+            }
+        }
+    }
+    if let Some(section) = section {
+        file.themes.push(finish(section)?);
+    }
+    Ok(file)
+}
+
+// `#rrggbb`
+fn parse_color(value: &str) -> Option<Rgb> {
+    let hex = value.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+// The theme called `name` (or the theme file's choice, or the default),
+// themes in the file taking precedence over built-in ones
+pub fn find_theme(name: Option<&str>, file: &ThemeFile) -> Result<Theme, String> {
+    let name = name.or(file.theme.as_deref()).unwrap_or(DEFAULT_THEME);
+    let built_in = built_in_themes();
+    file.themes
+        .iter()
+        .rev()
+        .chain(built_in.iter())
+        .find(|theme| theme.name == name)
+        .cloned()
+        .ok_or_else(|| {
+            let mut names: Vec<&str> = built_in.iter().map(|theme| theme.name.as_str()).collect();
+            names.extend(file.themes.iter().map(|theme| theme.name.as_str()));
+            format!("Unknown theme: {} (use {})", name, names.join(", "))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_files_define_themes() {
+        let file = parse_theme_file(
+            "theme = glados\n\
+             \n\
+             [glados]  # her favourite\n\
+             background = #101010\n\
+             lyrics = #FFB000\n\
+             art = #ffd040   # brighter\n",
+        )
+        .unwrap();
+        assert_eq!(file.theme.as_deref(), Some("glados"));
+        let theme = find_theme(None, &file).unwrap();
+        assert_eq!(
+            theme.colors,
+            Some(ThemeColors {
+                background: (16, 16, 16),
+                frame: (255, 176, 0),
+                lyrics: (255, 176, 0),
+                credits: (255, 176, 0),
+                art: (255, 208, 64),
+            })
+        );
+
+        let error = parse_theme_file("[glados]\nlyrics = orange\n").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(find_theme(Some("wheatley"), &file).is_err());
+    }
+
+    #[test]
+    fn colors_fall_back_to_what_the_terminal_has() {
+        let theme = find_theme(None, &ThemeFile::default()).unwrap();
+        let colors = |depth| Palette::new(&theme, depth).colors(Element::Lyrics, 0);
+
+        assert_eq!(
+            colors(ColorDepth::TrueColor),
+            Some((
+                Color::Rgb {
+                    r: 255,
+                    g: 176,
+                    b: 0
+                },
+                Color::Rgb { r: 0, g: 0, b: 0 }
+            ))
+        );
+        assert_eq!(
+            colors(ColorDepth::Ansi256),
+            Some((Color::AnsiValue(214), Color::AnsiValue(16)))
+        );
+        // The yellow on black of the original
+        assert_eq!(
+            colors(ColorDepth::Ansi16),
+            Some((Color::Yellow, Color::Black))
+        );
+        assert_eq!(colors(ColorDepth::Monochrome), None);
+        assert_eq!(ansi256((128, 128, 128)), 244);
+    }
+}
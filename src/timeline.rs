@@ -0,0 +1,166 @@
+// timeline.rs
+// Show timeline: what happens (a cue) and when it happens
+
+// What a timeline entry does when its time comes
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cue {
+    // Type a line of lyrics, optionally moving to the next line afterwards
+    Line { text: String, newline: bool },
+    // Show one of `ASCII_ART`
+    ShowArt(usize),
+    ClearLyrics,
+    StartMusic,
+    StartCredits,
+    End,
+}
+
+impl Cue {
+    // Build a cue from the numeric modes used by show scripts
+    pub fn from_mode(mode: u8, words: &str) -> Result<Self, String> {
+        match mode {
+            0 | 1 => Ok(Cue::Line {
+                text: words.to_string(),
+                newline: mode == 0,
+            }),
+            2 => words
+                .trim()
+                .parse::<usize>()
+                .map(Cue::ShowArt)
+                .map_err(|_| format!("art cue needs an art index, found `{}`", words)),
+            3 => Ok(Cue::ClearLyrics),
+            4 => Ok(Cue::StartMusic),
+            5 => Ok(Cue::StartCredits),
+            9 => Ok(Cue::End),
+            _ => Err(format!("unknown mode `{}`", mode)),
+        }
+    }
+}
+
+pub struct Lyric {
+    pub cue: Cue,
+    pub time: u32,
+    pub interval: f32,
+}
+
+impl Lyric {
+    pub fn new(cue: Cue, time: u32, interval: f32) -> Self {
+        Self {
+            cue,
+            time,
+            interval,
+        }
+    }
+}
+
+// Seconds spent typing each character of the line at `index`
+pub fn char_interval(lyrics: &[Lyric], index: usize) -> f32 {
+    let lyric = &lyrics[index];
+    let char_count = match &lyric.cue {
+        Cue::Line { text, .. } => std::cmp::max(text.chars().count(), 1),
+        _ => 1,
+    };
+
+    if lyric.interval < 0.0 {
+        // Spread the typing until the next cue starts
+        match lyrics.get(index + 1) {
+            Some(next) => next.time.saturating_sub(lyric.time) as f32 / 100.0 / char_count as f32,
+            None => 0.1,
+        }
+    } else {
+        lyric.interval / char_count as f32
+    }
+}
+
+// Everything on screen at some point of the show, used to redraw after a seek
+pub struct ShowState {
+    // Cue the show continues with
+    pub next_cue: usize,
+    // Characters of `next_cue` already typed when it is a line being typed
+    pub typed: usize,
+    // Lines on the current lyric page, the last one may still be typed on
+    pub lines: Vec<String>,
+    pub x: u16,
+    pub y: u16,
+    pub art: Option<usize>,
+    // Show times the music and the credits started at
+    pub music: Option<u32>,
+    pub credits: Option<u32>,
+}
+
+// Replay the timeline up to `time` (centiseconds) without any waiting
+pub fn state_at(lyrics: &[Lyric], time: u32) -> ShowState {
+    let mut state = ShowState {
+        next_cue: lyrics.len(),
+        typed: 0,
+        lines: vec![String::new()],
+        x: 0,
+        y: 0,
+        art: None,
+        music: None,
+        credits: None,
+    };
+
+    for (index, lyric) in lyrics.iter().enumerate() {
+        // Cues run once the show time has passed them
+        if time <= lyric.time || lyric.cue == Cue::End {
+            state.next_cue = index;
+            break;
+        }
+
+        match &lyric.cue {
+            Cue::Line { text, newline } => {
+                let total = text.chars().count();
+                let interval = char_interval(lyrics, index);
+                let elapsed = (time - lyric.time) as f32 / 100.0;
+                if interval > 0.0 && elapsed < interval * total as f32 {
+                    // Still typing, each character shows up before its wait
+                    let typed = std::cmp::min(total, (elapsed / interval) as usize + 1);
+                    if let Some(line) = state.lines.last_mut() {
+                        line.extend(text.chars().take(typed));
+                    }
+                    state.x += typed as u16;
+                    state.next_cue = index;
+                    state.typed = typed;
+                    break;
+                }
+
+                if let Some(line) = state.lines.last_mut() {
+                    line.push_str(text);
+                }
+                state.x += total as u16;
+                if *newline {
+                    state.lines.push(String::new());
+                    state.x = 0;
+                    state.y += 1;
+                }
+            }
+            Cue::ShowArt(art_index) => state.art = Some(*art_index),
+            Cue::ClearLyrics => {
+                state.lines = vec![String::new()];
+                state.x = 0;
+                state.y = 0;
+            }
+            Cue::StartMusic => state.music = Some(lyric.time),
+            Cue::StartCredits => state.credits = Some(lyric.time),
+            Cue::End => {}
+        }
+    }
+
+    state
+}
+
+// Show times worth seeking to: the start of every line, or of every page
+pub fn seek_points(lyrics: &[Lyric], by_page: bool) -> Vec<u32> {
+    let mut points = vec![0];
+    for lyric in lyrics {
+        let point = match &lyric.cue {
+            Cue::ClearLyrics => true,
+            Cue::Line { text, .. } => !by_page && !text.is_empty(),
+            _ => false,
+        };
+        if point && points.last() != Some(&lyric.time) {
+            points.push(lyric.time);
+        }
+    }
+    points
+}
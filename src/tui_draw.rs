@@ -1,347 +1,655 @@
-use crossterm::{
-    cursor,
-    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
-    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
-    ExecutableCommand,
-};
-use std::io::stdout;
-use std::sync::Mutex;
+use crossterm::terminal;
+use std::io;
+use std::sync::mpsc;
 use std::thread;
-use std::time::{Duration, Instant};
-use std::{
-    io::{self, Write},
-    process::exit,
-};
+use std::time::Duration;
 
+use crate::clock::SharedClock;
+use crate::compositor::{Compositor, Region};
 use crate::data::{ASCII_ART, CREDITS};
+use crate::effects::{self, Effects};
+use crate::layout::TerminalLayout;
+use crate::renderer::Renderer;
+use crate::term::TermProfile;
+use crate::theme::{Element, Palette};
 
-// Global state for drawing
-static mut IS_DRAW_END: bool = false;
-static mut CURSOR_X: u16 = 1;
-static mut CURSOR_Y: u16 = 1;
-
-static mut USEING_GLOBAL_LOCK: Mutex<bool> = Mutex::new(false);
-fn lock_ug() {
-    loop {
-        unsafe {
-            let mut iner_lock = USEING_GLOBAL_LOCK.lock().unwrap();
-            if *iner_lock == false {
-                *iner_lock = true;
-                return;
-            }
-            thread::sleep(Duration::from_millis(1));
-        }
-    }
+// How long the credits take to scroll through
+pub const CREDITS_DURATION: Duration = Duration::from_secs(174);
+
+type DrawCommand = Box<dyn FnOnce(&mut Screen) + Send>;
+
+// The one way to draw on the live show's screen: the screen belongs to a
+// render thread that runs drawing in the order it was asked for, so the
+// lyric and credits threads can't get in each other's way
+#[derive(Clone)]
+pub struct ScreenHandle {
+    sender: mpsc::Sender<DrawCommand>,
 }
 
-fn unlock_ug() {
-    loop {
-        unsafe {
-            let mut iner_lock = USEING_GLOBAL_LOCK.lock().unwrap();
-            if *iner_lock == true {
-                *iner_lock = false;
-                return;
+impl ScreenHandle {
+    pub fn spawn(mut screen: Screen) -> Self {
+        let (sender, receiver) = mpsc::channel::<DrawCommand>();
+        thread::spawn(move || {
+            while let Ok(command) = receiver.recv() {
+                command(&mut screen);
+                // Catch up with everything already asked for, then show it
+                // all at once
+                while let Ok(command) = receiver.try_recv() {
+                    if screen.ended {
+                        break;
+                    }
+                    command(&mut screen);
+                }
+                // Nothing may draw over the restored terminal
+                if screen.ended {
+                    break;
+                }
+                let _ = screen.present();
             }
-            thread::sleep(Duration::from_millis(1));
-        }
+        });
+        Self { sender }
+    }
+
+    // Queue some drawing, returns false once the show's drawing has ended
+    pub fn draw<F>(&self, draw: F) -> bool
+    where
+        F: FnOnce(&mut Screen) -> io::Result<()> + Send + 'static,
+    {
+        let command: DrawCommand = Box::new(move |screen| {
+            let _ = draw(screen);
+        });
+        self.sender.send(command).is_ok()
+    }
+
+    // Draw and wait until it is done, `None` once drawing has ended
+    pub fn draw_wait<R, F>(&self, draw: F) -> Option<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut Screen) -> R + Send + 'static,
+    {
+        let (reply, result) = mpsc::channel();
+        let command: DrawCommand = Box::new(move |screen| {
+            let _ = reply.send(draw(screen));
+        });
+        self.sender.send(command).ok()?;
+        result.recv().ok()
+    }
+
+    pub fn is_drawing(&self) -> bool {
+        self.draw(|_| Ok(()))
     }
 }
 
-pub struct Lyric {
-    pub words: String,
-    pub time: u32,
-    pub interval: f32,
-    pub mode: u8,
+// Something to draw the show on, with what it can do and where the lyrics
+// cursor is (0-based, on the lyric page). With cursor addressing everything
+// is drawn into a compositor and reaches the terminal in `present`; a
+// teletype is printed to directly. What the show is at (page, art, credits)
+// is kept so it can be drawn again at a new size.
+pub struct Screen {
+    renderer: Box<dyn Renderer>,
+    profile: TermProfile,
+    palette: Palette,
+    layout: TerminalLayout,
+    compositor: Compositor,
+    cursor_x: u16,
+    cursor_y: u16,
+    // Where the terminal's cursor is, if known
+    shown_cursor: Option<(u16, u16)>,
+    // The lyrics typed on the current page, by line
+    page: Vec<Vec<char>>,
+    art: Option<usize>,
+    // Credit characters shown, once they started
+    credits: Option<usize>,
+    status: Option<String>,
+    // CRT effects, when the terminal has the colors for them
+    effects: Option<Effects>,
+    // The terminal is big enough for `layout`, otherwise drawing only
+    // updates the above until it is again
+    fits: bool,
+    // The terminal was handed back, see `end_draw`
+    ended: bool,
 }
 
-impl Lyric {
-    pub fn new(words: &str, time: u32, interval: f32, mode: u8) -> Self {
+impl Screen {
+    pub fn new(
+        renderer: Box<dyn Renderer>,
+        profile: TermProfile,
+        palette: Palette,
+        effects: Option<Effects>,
+        layout: &TerminalLayout,
+    ) -> Self {
         Self {
-            words: words.to_string(),
-            time,
-            interval,
-            mode,
+            renderer,
+            profile,
+            palette,
+            layout: *layout,
+            compositor: Compositor::new(layout.columns, layout.lines),
+            cursor_x: 0,
+            cursor_y: 0,
+            shown_cursor: None,
+            page: Vec::new(),
+            art: None,
+            credits: None,
+            status: None,
+            effects,
+            fits: true,
+            ended: false,
         }
     }
-}
 
-// Terminal dimensions and layout
-#[derive(Clone)]
-pub struct TerminalLayout {
-    pub credits_width: u16,
-    pub credits_height: u16,
-    pub lyric_width: u16,
-    pub lyric_height: u16,
-    pub credits_pos_x: u16,
-    pub ascii_art_x: u16,
-    pub ascii_art_y: u16,
-}
+    pub fn begin_draw(&mut self) -> io::Result<()> {
+        if self.profile.alt_screen {
+            self.renderer.enter_alt_screen()?;
+        }
+        // A teletype has nothing but lyrics
+        if let Some((foreground, background)) = self.palette.colors(Element::Lyrics, 0) {
+            self.renderer.set_colors(foreground, background)?;
+        }
+        // The effects draw a cursor of their own
+        if self.effects.is_some() {
+            self.renderer.show_cursor(false)?;
+        }
+        self.renderer.flush()
+    }
 
-impl TerminalLayout {
-    pub fn new() -> Self {
-        let (columns, lines) = terminal::size().unwrap();
-
-        if columns < 80 || lines < 24 {
-            println!("Interrupt by user");
-            println!("This program required minimum 80 * 24 character array on your terminal.");
-            exit(0);
-        }
-        // let ascii_art_width = 40;
-        let ascii_art_height = 20;
-        let credits_width = std::cmp::min(columns - 43, 56);
-        let credits_height = lines - ascii_art_height - 2;
-        let lyric_width = columns - 8 - credits_width;
-        let lyric_height = lines - 2;
-        let credits_pos_x = lyric_width + 4;
-        let ascii_art_x = lyric_width + 3;
-        let ascii_art_y = credits_height + 3;
+    fn end_draw(&mut self) -> io::Result<()> {
+        self.ended = true;
+        if self.palette.is_colored() {
+            self.renderer.reset_colors()?;
+        }
+        if self.effects.is_some() {
+            self.renderer.show_cursor(true)?;
+        }
+        if self.profile.alt_screen {
+            self.renderer.leave_alt_screen()?;
+        } else if self.profile.cursor_addressing {
+            // Nothing to switch back to, leave a clean screen instead
+            self.renderer.clear_all()?;
+            self.renderer.move_to(1, 1)?;
+        } else {
+            self.renderer.print("\r\n")?;
+        }
+        self.renderer.flush()
+    }
 
-        Self {
-            credits_width,
-            credits_height,
-            lyric_width,
-            lyric_height,
-            credits_pos_x,
-            ascii_art_x,
-            ascii_art_y,
+    // Send everything drawn since last time to the terminal in one write,
+    // with the cursor left where the lyrics are typed
+    pub fn present(&mut self) -> io::Result<()> {
+        if self.profile.cursor_addressing {
+            if self
+                .compositor
+                .present(self.renderer.as_mut(), &self.palette)?
+            {
+                self.shown_cursor = None;
+            }
+            let lyrics = self.layout.lyrics;
+            let cursor = (
+                lyrics.x + std::cmp::min(self.cursor_x, lyrics.width - 1),
+                lyrics.y + self.cursor_y - self.page_top(),
+            );
+            if self.fits && self.shown_cursor != Some(cursor) {
+                self.renderer.move_to(cursor.0, cursor.1)?;
+                self.shown_cursor = Some(cursor);
+            }
         }
+        self.renderer.flush()
     }
-}
 
-pub fn begin_draw() -> io::Result<()> {
-    let mut stdout = io::stdout();
-    stdout.execute(EnterAlternateScreen)?;
-    stdout.execute(SetForegroundColor(Color::Yellow))?;
-    stdout.execute(SetBackgroundColor(Color::Black))?;
-    stdout.flush()?;
-    Ok(())
-}
+    // The terminal is now `columns` x `lines`, lay the show out again or ask
+    // for a bigger window. Returns whether the show fits.
+    pub fn resize(&mut self, columns: u16, lines: u16) -> io::Result<bool> {
+        if !self.profile.cursor_addressing {
+            return Ok(true);
+        }
+        self.compositor = Compositor::new(columns, lines);
+        match TerminalLayout::fit(self.layout.options, columns, lines) {
+            Some(layout) => {
+                self.layout = layout;
+                self.fits = true;
+            }
+            None => self.fits = false,
+        }
+        self.redraw()?;
+        Ok(self.fits)
+    }
 
-pub fn end_draw() -> io::Result<()> {
-    unsafe {
-        lock_ug();
-        IS_DRAW_END = true;
-        unlock_ug();
-    }
-    let mut stdout = io::stdout();
-    stdout.execute(ResetColor)?;
-    stdout.execute(LeaveAlternateScreen)?;
-    stdout.flush()?;
-    Ok(())
-}
+    // Clear the terminal and draw everything the show is at again
+    fn redraw(&mut self) -> io::Result<()> {
+        self.renderer.clear_all()?;
+        self.compositor.cleared();
+        self.shown_cursor = None;
+
+        if !self.fits {
+            let (columns, lines) = self.compositor.size();
+            let (min_columns, min_lines) = TerminalLayout::min_size(self.layout.options);
+            let long = format!(
+                "Paused, please make the terminal at least {}x{}",
+                min_columns, min_lines
+            );
+            let short = format!("Paused, {}x{}", min_columns, min_lines);
+            let message = if long.len() <= columns as usize {
+                long
+            } else {
+                short
+            };
+            let x = columns.saturating_sub(message.len() as u16) / 2 + 1;
+            self.compositor.print(
+                Region::new(1, 1, columns, lines),
+                x,
+                lines / 2 + 1,
+                &message,
+                Element::Lyrics,
+            );
+            return Ok(());
+        }
 
-pub fn move_cursor(x: u16, y: u16) -> io::Result<()> {
-    let mut stdout = io::stdout();
-    stdout.execute(cursor::MoveTo(x - 1, y - 1))?;
-    unsafe {
-        lock_ug();
-        CURSOR_X = x;
-        CURSOR_Y = y;
-        unlock_ug();
-    }
-    stdout.flush()?;
-    Ok(())
-}
+        self.draw_frame()?;
+        if let Some(status) = self.status.clone() {
+            self.draw_status(&status)?;
+        }
+        self.draw_page();
+        self.redraw_ascii_art(self.art)?;
+        if let Some(shown) = self.credits {
+            self.redraw_credits(shown)?;
+        }
+        Ok(())
+    }
 
-pub fn clear_screen() -> io::Result<()> {
-    // let mut stdout = io::stdout();
-    stdout().execute(Clear(ClearType::All))?;
-    unsafe {
-        lock_ug();
-        CURSOR_X = 1;
-        CURSOR_Y = 1;
-        unlock_ug();
-    }
-    stdout().flush()?;
-    Ok(())
-}
+    pub fn move_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
+        self.cursor_x = x;
+        self.cursor_y = y;
+        // The page scrolls along when the pane is too short for it
+        self.draw_page();
+        Ok(())
+    }
 
-pub fn print_at(text: &str, newline: bool) -> io::Result<()> {
-    let mut stdout = io::stdout();
-    if newline {
-        stdout.execute(Print(text))?;
-        stdout.execute(Print("\n"))?;
-        unsafe {
-            lock_ug();
-            CURSOR_X = 1;
-            CURSOR_Y += 1;
-            unlock_ug();
+    pub fn clear_screen(&mut self) -> io::Result<()> {
+        if self.profile.cursor_addressing {
+            self.renderer.clear_all()?;
+            self.compositor.cleared();
+            self.shown_cursor = None;
         }
-    } else {
-        stdout.execute(Print(text))?;
-        unsafe {
-            lock_ug();
-            CURSOR_X += text.len() as u16;
-            unlock_ug();
+        self.page.clear();
+        self.clear_effects();
+        self.art = None;
+        self.credits = None;
+        self.status = None;
+        self.cursor_x = 0;
+        self.cursor_y = 0;
+        Ok(())
+    }
+
+    // Print at the cursor of a teletype, which has nothing to composite
+    pub fn print_at(&mut self, text: &str, newline: bool) -> io::Result<()> {
+        self.renderer.print(text)?;
+        if newline {
+            // Raw mode turns off the terminal's own carriage return on newline
+            self.renderer.print("\r\n")?;
+            self.cursor_x = 0;
+            self.cursor_y += 1;
+        } else {
+            self.cursor_x += text.chars().count() as u16;
+        }
+        Ok(())
+    }
+
+    // Type `text` at (`x`, `y`) on the lyric page, leaving the cursor after
+    // it
+    fn type_at(&mut self, x: u16, y: u16, text: &str) -> io::Result<()> {
+        if !self.profile.cursor_addressing {
+            self.renderer.print(text)?;
+        }
+
+        let row = y as usize;
+        if self.page.len() <= row {
+            self.page.resize(row + 1, Vec::new());
+        }
+        let line = &mut self.page[row];
+        for (i, ch) in text.chars().enumerate() {
+            let column = x as usize + i;
+            if line.len() <= column {
+                line.resize(column + 1, ' ');
+            }
+            line[column] = ch;
+            if let Some(effects) = self.effects.as_mut() {
+                effects.typed(column as u16, y);
+            }
+        }
+
+        self.move_cursor(x + text.chars().count() as u16, y)
+    }
+
+    // First page line in view, the page scrolls up once the cursor goes
+    // past the bottom of the pane
+    fn page_top(&self) -> u16 {
+        (self.cursor_y + 1).saturating_sub(self.layout.lyrics.height)
+    }
+
+    // Show the part of the page in view in the lyrics pane
+    fn draw_page(&mut self) {
+        let region = self.layout.lyrics;
+        let top = self.page_top() as usize;
+        self.clear_in(region);
+        for (dy, line) in self.page.iter().skip(top).enumerate() {
+            if dy >= region.height as usize {
+                break;
+            }
+            let line: String = line.iter().collect();
+            if self.profile.cursor_addressing && self.fits {
+                self.compositor.print(
+                    region,
+                    region.x,
+                    region.y + dy as u16,
+                    &line,
+                    Element::Lyrics,
+                );
+            }
+        }
+
+        let Some(effects) = self.effects.as_ref().filter(|_| self.fits) else {
+            return;
+        };
+        let top = top as u16;
+        for (x, y, glow) in effects.glowing() {
+            if y >= top && y - top < region.height && x < region.width {
+                self.compositor
+                    .shade(region.x + x, region.y + y - top, glow);
+            }
+        }
+        if effects.cursor_on() {
+            let x = region.x + std::cmp::min(self.cursor_x, region.width - 1);
+            let y = region.y + self.cursor_y - top;
+            self.compositor
+                .print(region, x, y, &effects::CURSOR.to_string(), Element::Lyrics);
+        }
+    }
+
+    // Bring the effects to show time `now`
+    fn tick(&mut self, now: Duration) {
+        let Some(effects) = self.effects.as_mut() else {
+            return;
+        };
+        effects.tick(now);
+        let shade = if effects.flickers() {
+            effects::FLICKER
+        } else {
+            0
+        };
+        self.compositor.set_overall_shade(shade);
+        self.draw_page();
+    }
+
+    // Nothing on the page is freshly typed any more
+    fn clear_effects(&mut self) {
+        if let Some(effects) = self.effects.as_mut() {
+            effects.clear();
+        }
+    }
+
+    // Draw into one region of the screen, leaving the others alone
+    fn draw_in(&mut self, region: Region, x: u16, y: u16, text: &str, element: Element) {
+        if self.profile.cursor_addressing && self.fits {
+            self.compositor.print(region, x, y, text, element);
         }
     }
-    stdout.flush()?;
-    Ok(())
-}
 
-pub fn draw_frame(layout: &TerminalLayout) -> io::Result<()> {
-    move_cursor(1, 1)?;
-
-    // Top border
-    let top_line = format!(
-        " {} {} ",
-        "-".repeat(layout.lyric_width as usize),
-        "-".repeat(layout.credits_width as usize)
-    );
-    print_at(&top_line, true)?;
-
-    // Credits area borders
-    for _ in 0..layout.credits_height {
-        let line = format!(
-            "|{}||{}|",
-            " ".repeat(layout.lyric_width as usize),
-            " ".repeat(layout.credits_width as usize)
+    fn clear_in(&mut self, region: Region) {
+        if self.profile.cursor_addressing && self.fits {
+            self.compositor.clear(region);
+        }
+    }
+
+    // A border around every pane that has one
+    pub fn draw_frame(&mut self) -> io::Result<()> {
+        let screen = self.layout.screen();
+        for pane in self.layout.framed() {
+            let (left, right) = (pane.x - 1, pane.x + pane.width);
+            let edge = format!(" {} ", "-".repeat(pane.width as usize));
+            self.draw_in(screen, left, pane.y - 1, &edge, Element::Frame);
+            for y in pane.y..pane.y + pane.height {
+                self.draw_in(screen, left, y, "|", Element::Frame);
+                self.draw_in(screen, right, y, "|", Element::Frame);
+            }
+            self.draw_in(screen, left, pane.y + pane.height, &edge, Element::Frame);
+        }
+        Ok(())
+    }
+
+    // Show a short notice at the right end of the top border, e.g. that the
+    // music can't play
+    pub fn draw_status(&mut self, status: &str) -> io::Result<()> {
+        self.status = Some(status.to_string());
+        let text = format!(" {} ", status);
+        let top = self.layout.stage.y;
+        let right = self
+            .layout
+            .framed()
+            .iter()
+            .filter(|pane| pane.y == top + 1)
+            .map(|pane| pane.x + pane.width - 2)
+            .max()
+            .unwrap_or(self.layout.columns);
+        self.draw_in(
+            self.layout.screen(),
+            right.saturating_sub(text.len() as u16),
+            top,
+            &text,
+            Element::Frame,
         );
-        print_at(&line, true)?;
+        Ok(())
     }
 
-    // Middle border
-    let middle_line = format!(
-        "|{}| {} ",
-        " ".repeat(layout.lyric_width as usize),
-        "-".repeat(layout.credits_width as usize)
-    );
-    print_at(&middle_line, true)?;
+    pub fn clear_lyrics(&mut self) -> io::Result<()> {
+        self.page.clear();
+        self.clear_effects();
+        // A teletype starts the next page after a blank line
+        if !self.profile.cursor_addressing {
+            return self.print_at("", true);
+        }
+        self.move_cursor(0, 0)
+    }
 
-    // Remaining lyric area
-    for _ in 0..(layout.lyric_height - 1 - layout.credits_height) {
-        let line = format!("|{}|", " ".repeat(layout.lyric_width as usize));
-        print_at(&line, true)?;
+    // Redraw a whole lyric page at once, e.g. after seeking
+    pub fn redraw_lyrics(&mut self, lines: &[String]) -> io::Result<()> {
+        self.clear_lyrics()?;
+        if !self.profile.cursor_addressing {
+            return self.print_at(&lines.join("\r\n"), false);
+        }
+        self.page = lines.iter().map(|line| line.chars().collect()).collect();
+        self.draw_page();
+        Ok(())
     }
 
-    // Bottom border
-    let bottom_line = format!(" {} ", "-".repeat(layout.lyric_width as usize));
-    print_at(&bottom_line, false)?;
+    // Draw line `dy` of the art, every character as a block of `art_scale`
+    // cells across and down
+    fn draw_art_line(&mut self, art_index: usize, dy: usize) -> io::Result<()> {
+        if let Some(art) = ASCII_ART.get(art_index) {
+            self.art = Some(art_index);
+            if let Some(region) = self.layout.art {
+                let scale = self.layout.options.art_scale;
+                let line: String = art[dy]
+                    .chars()
+                    .flat_map(|ch| std::iter::repeat_n(ch, scale as usize))
+                    .collect();
+                for k in 0..scale {
+                    let y = region.y + dy as u16 * scale + k;
+                    self.draw_in(region, region.x, y, &line, Element::Art);
+                }
+            }
+        }
+        Ok(())
+    }
 
-    move_cursor(2, 2)?;
-    thread::sleep(Duration::from_millis(1000));
+    // Draw the art right away, or blank the art area for `None`
+    pub fn redraw_ascii_art(&mut self, art_index: Option<usize>) -> io::Result<()> {
+        match art_index {
+            Some(art_index) => {
+                for dy in 0..ASCII_ART[0].len() {
+                    self.draw_art_line(art_index, dy)?;
+                }
+            }
+            None => {
+                self.art = None;
+                if let Some(region) = self.layout.art {
+                    self.clear_in(region);
+                }
+            }
+        }
+        Ok(())
+    }
 
-    Ok(())
+    // Redraw the credits as they are `shown` characters in, lines are
+    // aligned to the bottom of the area. Only what changed reaches the
+    // terminal, so scrolling doesn't blank the area.
+    pub fn redraw_credits(&mut self, shown: usize) -> io::Result<()> {
+        self.credits = Some(shown);
+        let Some(region) = self.layout.credits else {
+            return Ok(());
+        };
+        let last_credits = credits_tail(region.height as usize, shown);
+        self.clear_in(region);
+        for (k, line) in last_credits.iter().enumerate() {
+            let y = region.y + region.height - last_credits.len() as u16 + k as u16;
+            self.draw_in(region, region.x, y, line, Element::Credits);
+        }
+        Ok(())
+    }
 }
 
-pub fn clear_lyrics(layout: &TerminalLayout) -> io::Result<()> {
-    move_cursor(1, 2)?;
-    for _ in 0..layout.lyric_height {
-        let line = format!("|{}", " ".repeat(layout.lyric_width as usize));
-        print_at(&line, true)?;
-    }
-    move_cursor(2, 2)?;
-    Ok(())
+// Hand the terminal back, waiting until it is restored; drawing asked for
+// afterwards is dropped
+pub fn end_draw(screen: &ScreenHandle) -> io::Result<()> {
+    let _ = terminal::disable_raw_mode();
+    screen
+        .draw_wait(|screen| screen.end_draw())
+        .unwrap_or(Ok(()))
 }
 
-pub fn draw_lyrics(text: &str, x: u16, y: u16, interval: f32, newline: bool) -> io::Result<u16> {
+// Type `text` one character per `interval` seconds, stopping early when a
+// seek interrupts the typing. Returns the column typing stopped at.
+pub fn draw_lyrics(
+    screen: &ScreenHandle,
+    clock: &SharedClock,
+    text: &str,
+    x: u16,
+    y: u16,
+    interval: f32,
+    newline: bool,
+) -> u16 {
     let mut current_x = x;
-    move_cursor(current_x + 2, y + 2)?;
+    screen.draw(move |screen| screen.move_cursor(x, y));
 
-    for ch in text.chars() {
-        move_cursor(current_x + 2, y + 2)?;
-        print_at(&ch.to_string(), false)?;
-        thread::sleep(Duration::from_secs_f32(interval));
+    // Schedule every character from the same start so sleeps don't drift, in
+    // whole milliseconds so float noise can't make one late for its step
+    let start = clock.now();
+    for (i, ch) in text.chars().enumerate() {
+        screen.draw(move |screen| screen.type_at(current_x, y, &ch.to_string()));
         current_x += 1;
+        let due = (interval * 1000.0 * (i + 1) as f32).round() as u64;
+        if !clock.sleep_until(start + Duration::from_millis(due)) {
+            return current_x;
+        }
     }
 
     if newline {
         current_x = 0;
-        move_cursor(current_x + 2, y + 3)?;
+        screen.draw(move |screen| {
+            if screen.profile.cursor_addressing {
+                screen.move_cursor(0, y + 1)
+            } else {
+                screen.print_at("", true)
+            }
+        });
     }
 
-    Ok(current_x)
+    current_x
 }
 
-pub fn draw_ascii_art(layout: &TerminalLayout, art_index: usize) -> io::Result<()> {
+pub fn draw_ascii_art(screen: &ScreenHandle, clock: &SharedClock, art_index: usize) {
     if art_index >= ASCII_ART.len() {
-        return Ok(());
+        return;
     }
 
-    let art = ASCII_ART[art_index];
-    for (dy, line) in art.iter().enumerate() {
-        move_cursor(layout.ascii_art_x, layout.ascii_art_y + dy as u16)?;
-        print_at(line, false)?;
-        thread::sleep(Duration::from_millis(10));
+    for dy in 0..ASCII_ART[art_index].len() {
+        screen.draw(move |screen| screen.draw_art_line(art_index, dy));
+        if !clock.sleep(Duration::from_millis(10)) {
+            break;
+        }
     }
-    Ok(())
 }
 
-pub fn start_credits(layout: TerminalLayout) {
-    thread::spawn(move || {
-        let mut credit_x = 0;
-        let mut i = 0;
-        let length = CREDITS.len();
-        let mut last_credits: Vec<String> = vec!["".to_string()];
-        let start_time = Instant::now();
-
-        for ch in CREDITS.chars() {
-            let current_time =
-                start_time + Duration::from_secs_f64(174.0 / length as f64 * i as f64);
-            i += 1;
-
-            if ch == '\n' {
-                credit_x = 0;
-                last_credits.push("".to_string());
-                if last_credits.len() > layout.credits_height as usize {
-                    last_credits = last_credits
-                        [(last_credits.len() - layout.credits_height as usize)..]
-                        .to_vec();
-                }
-
-                unsafe {
-                    lock_ug();
-                    if IS_DRAW_END {
-                        break;
-                    }
-                    unlock_ug();
-                }
+// Last lines of the credits once `shown` characters have been typed
+fn credits_tail(height: usize, shown: usize) -> Vec<String> {
+    let mut last_credits: Vec<String> = vec!["".to_string()];
+    for ch in CREDITS.chars().take(shown) {
+        if ch == '\n' {
+            last_credits.push("".to_string());
+            if last_credits.len() > height {
+                last_credits.remove(0);
+            }
+        } else if let Some(last_line) = last_credits.last_mut() {
+            last_line.push(ch);
+        }
+    }
+    last_credits
+}
 
-                // Clear and redraw credits area
-                for y in 2..(2 + layout.credits_height - last_credits.len() as u16) {
-                    let _ = move_cursor(layout.credits_pos_x, y);
-                    let _ = print_at(&" ".repeat(layout.credits_width as usize), false);
-                }
+// Number of credit characters on screen at show time `now`, when they
+// scroll from `start` for `duration`
+pub fn credits_shown_at(start: Duration, duration: Duration, now: Duration) -> usize {
+    let length = CREDITS.chars().count();
+    if now < start {
+        0
+    } else {
+        let elapsed = (now - start).as_secs_f64() / duration.as_secs_f64();
+        std::cmp::min(length, (elapsed * length as f64) as usize + 1)
+    }
+}
 
-                for (k, line) in last_credits.iter().enumerate() {
-                    let y = 2 + layout.credits_height - last_credits.len() as u16 + k as u16;
-                    let _ = move_cursor(layout.credits_pos_x, y);
-                    let _ = print_at(line, false);
-                    let padding = layout.credits_width as usize - line.len();
-                    if padding > 0 {
-                        let _ = print_at(&" ".repeat(padding), false);
-                    }
-                }
+// Keep the CRT effects going with the show clock until drawing ends
+pub fn start_effects(screen: ScreenHandle, clock: SharedClock) {
+    thread::spawn(move || loop {
+        let now = clock.now();
+        if !screen.draw(move |screen| {
+            screen.tick(now);
+            Ok(())
+        }) {
+            break;
+        }
+        clock.sleep(effects::FRAME);
+    });
+}
 
-                unsafe {
-                    let _ = move_cursor(CURSOR_X, CURSOR_Y);
-                }
+// Scroll the credits from show time `start` for `duration`, following the
+// clock through pauses and seeks until drawing ends
+pub fn start_credits(
+    screen: ScreenHandle,
+    clock: SharedClock,
+    start: Duration,
+    duration: Duration,
+) {
+    thread::spawn(move || {
+        let length = CREDITS.chars().count();
+        let char_time = |i: usize| start + duration.mul_f64(i as f64 / length as f64);
+
+        let mut shown = credits_shown_at(start, duration, clock.now());
+        let mut seeks = clock.seeks();
+        screen.draw(move |screen| screen.redraw_credits(shown));
+
+        while screen.is_drawing() {
+            if clock.seeks() != seeks {
+                seeks = clock.seeks();
+                shown = credits_shown_at(start, duration, clock.now());
+            } else if shown >= length {
+                // Everything is typed, stay around in case of a seek back
+                clock.sleep(Duration::from_millis(100));
+                continue;
+            } else if clock.sleep_until(char_time(shown)) {
+                shown += 1;
             } else {
-                if let Some(last_line) = last_credits.last_mut() {
-                    last_line.push(ch);
-                }
-
-                unsafe {
-                    lock_ug();
-                    if IS_DRAW_END {
-                        break;
-                    }
-                    unlock_ug();
-                }
-
-                let _ = move_cursor(layout.credits_pos_x + credit_x, layout.credits_height + 1);
-                let _ = print_at(&ch.to_string(), false);
-
-                unsafe {
-                    let _ = move_cursor(CURSOR_X, CURSOR_Y);
-                }
-
-                credit_x += 1;
-            }
-
-            while Instant::now() < current_time {
-                thread::sleep(Duration::from_millis(10));
+                continue;
             }
+            screen.draw(move |screen| screen.redraw_credits(shown));
         }
     });
 }
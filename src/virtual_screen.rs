@@ -0,0 +1,148 @@
+// virtual_screen.rs
+// A terminal that only exists in memory, for rendering the show without
+// one (tests, --dump-frame, render)
+
+use crossterm::style::Color;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use crate::renderer::Renderer;
+
+struct Grid {
+    cells: Vec<Vec<char>>,
+    // Foreground each cell was printed in, `None` for the default
+    inks: Vec<Vec<Option<Color>>>,
+    foreground: Option<Color>,
+    // 0-based cursor position
+    x: usize,
+    y: usize,
+    // Counts prints and clears, to tell when there is something new
+    changes: u64,
+}
+
+// Clones share the same grid, so one can be handed to a `Screen` and the
+// other kept to read what was drawn
+#[derive(Clone)]
+pub struct VirtualScreen {
+    grid: Arc<Mutex<Grid>>,
+}
+
+impl VirtualScreen {
+    pub fn new(columns: u16, lines: u16) -> Self {
+        Self {
+            grid: Arc::new(Mutex::new(Grid {
+                cells: vec![vec![' '; columns as usize]; lines as usize],
+                inks: vec![vec![None; columns as usize]; lines as usize],
+                foreground: None,
+                x: 0,
+                y: 0,
+                changes: 0,
+            })),
+        }
+    }
+
+    // Every line of the screen, without trailing blanks
+    pub fn contents(&self) -> String {
+        let grid = self.grid.lock().unwrap();
+        let mut text = String::new();
+        for row in &grid.cells {
+            let line: String = row.iter().collect();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    // Every cell with the color it was printed in
+    pub fn cells(&self) -> Vec<Vec<(char, Option<Color>)>> {
+        let grid = self.grid.lock().unwrap();
+        grid.cells
+            .iter()
+            .zip(&grid.inks)
+            .map(|(row, inks)| row.iter().copied().zip(inks.iter().copied()).collect())
+            .collect()
+    }
+
+    pub fn changes(&self) -> u64 {
+        self.grid.lock().unwrap().changes
+    }
+}
+
+impl Renderer for VirtualScreen {
+    fn move_to(&mut self, x: u16, y: u16) -> io::Result<()> {
+        let mut grid = self.grid.lock().unwrap();
+        grid.x = x.saturating_sub(1) as usize;
+        grid.y = std::cmp::min(y.saturating_sub(1) as usize, grid.cells.len() - 1);
+        Ok(())
+    }
+
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        let mut grid = self.grid.lock().unwrap();
+        let columns = grid.cells[0].len();
+        let lines = grid.cells.len();
+        grid.changes += 1;
+        for ch in text.chars() {
+            match ch {
+                '\r' => grid.x = 0,
+                '\n' => grid.y = std::cmp::min(grid.y + 1, lines - 1),
+                // Other control characters (e.g. the script's \0 pauses)
+                // don't show up on a terminal either
+                _ if ch.is_control() => {}
+                _ => {
+                    // Wrap at the right margin like a terminal does
+                    if grid.x >= columns {
+                        grid.x = 0;
+                        grid.y = std::cmp::min(grid.y + 1, lines - 1);
+                    }
+                    let (x, y) = (grid.x, grid.y);
+                    grid.cells[y][x] = ch;
+                    grid.inks[y][x] = grid.foreground;
+                    grid.x += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn clear_all(&mut self) -> io::Result<()> {
+        let mut grid = self.grid.lock().unwrap();
+        for row in grid.cells.iter_mut() {
+            row.fill(' ');
+        }
+        for row in grid.inks.iter_mut() {
+            row.fill(None);
+        }
+        grid.changes += 1;
+        Ok(())
+    }
+
+    // The background is the same everywhere, only the text's color is kept
+    fn set_colors(&mut self, foreground: Color, _background: Color) -> io::Result<()> {
+        self.grid.lock().unwrap().foreground = Some(foreground);
+        Ok(())
+    }
+
+    fn reset_colors(&mut self) -> io::Result<()> {
+        self.grid.lock().unwrap().foreground = None;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prints_like_a_terminal() {
+        let mut screen = VirtualScreen::new(4, 2);
+        screen.move_to(3, 1).unwrap();
+        // Wraps at the right margin, NUL shows nothing
+        screen.print("ab\0cd").unwrap();
+        screen.print("\r\ne").unwrap();
+        assert_eq!(screen.contents(), "  ab\ned\n");
+    }
+}